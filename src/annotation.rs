@@ -0,0 +1,345 @@
+use std::{any::Any, cell::RefCell, rc::Rc};
+
+use raylib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    colorscheme::ColorSchemes,
+    gui::draw_text_wordwrap,
+    history::Command,
+    objects::{Camera, Object},
+    save::AnnotationSave,
+    settings::Settings,
+    structs::Vector2,
+    window::EDITOR_STATE,
+};
+
+const HEADER_HEIGHT: f32 = 24.0;
+const RESIZE_HANDLE: f32 = 12.0;
+const MIN_SIZE: [f32; 2] = [80.0, 48.0];
+pub const DOUBLE_CLICK_TIME: f64 = 0.4;
+const FONT_SIZE: i32 = 18;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    /// Sticky note with word-wrapped text.
+    Note,
+    /// Titled rectangle; moving it moves the nodes inside.
+    Frame,
+}
+
+impl AnnotationKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnnotationKind::Note => "note",
+            AnnotationKind::Frame => "frame",
+        }
+    }
+
+    /// Frames sit below notes, both below nodes.
+    pub fn z(&self) -> i32 {
+        match self {
+            AnnotationKind::Note => -10,
+            AnnotationKind::Frame => -20,
+        }
+    }
+}
+
+/// Canvas object that never takes part in evaluation.
+pub struct Annotation {
+    pub id: String,
+    pub kind: AnnotationKind,
+    pub position: Vector2,
+    pub size: Vector2,
+    /// Note body or frame title.
+    pub text: String,
+    pub z: i32,
+    pub font: Rc<RefCell<Font>>,
+    pub color_schemes: Rc<RefCell<ColorSchemes>>,
+    pub settings: Rc<RefCell<Settings>>,
+    pub editing: bool,
+    /// Mouse offset when the header was pressed this frame; the window drags the annotation.
+    pub grabbed: Option<Vector2>,
+    /// Set for the frame the header was right-clicked in; the window opens the context menu.
+    pub context_requested: bool,
+    /// Set by the window while the pointer is over a node, which then takes double clicks.
+    pub under_node: bool,
+    resizing: bool,
+    edit_start: Option<AnnotationSave>,
+    last_click: f64,
+}
+
+impl Object for Annotation {
+    fn draw(&self, d: &mut RaylibDrawHandle, _camera: &Camera) {
+        let schemes = self.color_schemes.borrow();
+        let settings = self.settings.borrow();
+        let color = |key: &str| {
+            schemes
+                .get_color(&settings.scheme, &format!("{}_{}", self.kind.name(), key))
+                .unwrap_or(Color::MAGENTA)
+        };
+        let rect = self.rect();
+        let font = self.font.borrow();
+        let text = if self.editing {
+            format!("{}_", self.text)
+        } else {
+            self.text.clone()
+        };
+
+        d.draw_rectangle_rec(rect, color("background"));
+        d.draw_rectangle_rec(self.header(), color("header"));
+
+        match self.kind {
+            AnnotationKind::Note => draw_text_wordwrap(
+                d,
+                &text,
+                (rect.x + 8.0) as i32,
+                (rect.y + HEADER_HEIGHT + 4.0) as i32,
+                (rect.width - 16.0) as i32,
+                &font,
+                FONT_SIZE,
+                color("foreground"),
+                2,
+            ),
+            AnnotationKind::Frame => d.draw_text_ex(
+                &*font,
+                &text,
+                Vector2::new(rect.x + 8.0, rect.y + 3.0, None),
+                FONT_SIZE as f32,
+                1.0,
+                color("foreground"),
+            ),
+        }
+
+        let corner = Vector2::new(rect.x + rect.width, rect.y + rect.height, None);
+        for offset in [RESIZE_HANDLE, RESIZE_HANDLE / 2.0] {
+            d.draw_line_ex(
+                corner.clone() - Vector2::new(offset, 2.0, None),
+                corner.clone() - Vector2::new(2.0, offset, None),
+                1.0,
+                color("border"),
+            );
+        }
+
+        d.draw_rectangle_lines_ex(rect, if self.editing { 2.0 } else { 1.0 }, color("border"));
+    }
+
+    fn update(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread, camera: &Camera) {
+        let mouse = Vector2::from(rl.get_screen_to_world2D(rl.get_mouse_position(), camera));
+        let header = self.header();
+        let handle = Rectangle::new(
+            self.position.x + self.size.x - RESIZE_HANDLE,
+            self.position.y + self.size.y - RESIZE_HANDLE,
+            RESIZE_HANDLE,
+            RESIZE_HANDLE,
+        );
+        let text_area = match self.kind {
+            AnnotationKind::Note => self.rect(),
+            AnnotationKind::Frame => header,
+        };
+
+        self.grabbed = None;
+        self.context_requested = false;
+
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            let now = rl.get_time();
+
+            if self.editing && !text_area.check_collision_point_rec(mouse.clone()) {
+                self.finish_edit();
+            }
+
+            if handle.check_collision_point_rec(mouse.clone()) {
+                self.resizing = true;
+                self.begin_edit();
+            } else if text_area.check_collision_point_rec(mouse.clone())
+                && !self.under_node
+                && now - self.last_click <= DOUBLE_CLICK_TIME
+            {
+                self.editing = true;
+                self.begin_edit();
+            } else if header.check_collision_point_rec(mouse.clone()) && !self.editing {
+                self.grabbed = Some(mouse.clone() - self.position.clone());
+            }
+
+            self.last_click = if self.rect().check_collision_point_rec(mouse.clone()) {
+                now
+            } else {
+                0.0
+            };
+        }
+
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
+            && header.check_collision_point_rec(mouse.clone())
+        {
+            self.context_requested = true;
+        }
+
+        if self.resizing {
+            if rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+                self.size = Vector2::new(
+                    (mouse.x - self.position.x).max(MIN_SIZE[0]),
+                    (mouse.y - self.position.y).max(MIN_SIZE[1]),
+                    None,
+                );
+            } else {
+                self.resizing = false;
+                if !self.editing {
+                    self.record_edit();
+                }
+            }
+        }
+
+        if !self.editing {
+            return;
+        }
+
+        while let Some(key) = rl.get_key_pressed() {
+            match key {
+                KeyboardKey::KEY_BACKSPACE => {
+                    self.text.pop();
+                }
+                KeyboardKey::KEY_ENTER | KeyboardKey::KEY_KP_ENTER | KeyboardKey::KEY_ESCAPE => {
+                    self.finish_edit();
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        while let Some(ch) = rl.get_char_pressed() {
+            if !ch.is_control() {
+                self.text.push(ch);
+            }
+        }
+    }
+
+    fn set_property(&mut self, key: String, value: Box<dyn Any>) {
+        match key.as_str() {
+            "position" => {
+                if let Ok(v) = value.downcast::<Vector2>() {
+                    self.position = *v;
+                }
+            }
+            "size" => {
+                if let Ok(v) = value.downcast::<Vector2>() {
+                    self.size = *v;
+                }
+            }
+            "text" => {
+                if let Ok(v) = value.downcast::<String>() {
+                    self.text = *v;
+                }
+            }
+            "z" => {
+                if let Ok(v) = value.downcast::<i32>() {
+                    self.z = *v;
+                }
+            }
+            _ => eprintln!("set_property: bilinmeyen anahtar '{}'", key),
+        }
+    }
+
+    fn get_property(&self, key: String) -> Box<dyn Any + 'static> {
+        match key.as_str() {
+            "position" => Box::new(self.position.clone()),
+            "size" => Box::new(self.size.clone()),
+            "text" => Box::new(self.text.clone()),
+            "id" => Box::new(self.id.clone()),
+            "z" => Box::new(self.z),
+            _ => Box::new(()),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Annotation {
+    pub fn new(
+        save: &AnnotationSave,
+        font: Rc<RefCell<Font>>,
+        color_schemes: Rc<RefCell<ColorSchemes>>,
+        settings: Rc<RefCell<Settings>>,
+    ) -> Self {
+        Self {
+            id: save.id.clone(),
+            kind: save.kind,
+            position: save.position.into(),
+            size: save.size.into(),
+            text: save.text.clone(),
+            z: save.kind.z(),
+            font,
+            color_schemes,
+            settings,
+            editing: false,
+            grabbed: None,
+            context_requested: false,
+            under_node: false,
+            resizing: false,
+            edit_start: None,
+            last_click: 0.0,
+        }
+    }
+
+    pub fn rect(&self) -> Rectangle {
+        Rectangle::new(self.position.x, self.position.y, self.size.x, self.size.y)
+    }
+
+    fn header(&self) -> Rectangle {
+        Rectangle::new(self.position.x, self.position.y, self.size.x, HEADER_HEIGHT)
+    }
+
+    /// Starts typing into the note body or frame title, as a double click does.
+    pub fn begin_text_edit(&mut self) {
+        self.editing = true;
+        self.begin_edit();
+    }
+
+    /// Whether the annotation is being resized or typed into.
+    pub fn busy(&self) -> bool {
+        self.resizing || self.editing
+    }
+
+    /// Takes the fields of an undo/redo step, dropping any edit in progress.
+    pub fn apply(&mut self, save: &AnnotationSave) {
+        self.position = save.position.into();
+        self.size = save.size.into();
+        self.text = save.text.clone();
+        self.editing = false;
+        self.resizing = false;
+        self.edit_start = None;
+    }
+
+    fn begin_edit(&mut self) {
+        if self.edit_start.is_none() {
+            self.edit_start = Some(AnnotationSave::from_annotation(self));
+        }
+    }
+
+    fn finish_edit(&mut self) {
+        self.editing = false;
+        self.record_edit();
+    }
+
+    fn record_edit(&mut self) {
+        let Some(before) = self.edit_start.take() else {
+            return;
+        };
+        let after = AnnotationSave::from_annotation(self);
+
+        if before != after {
+            EDITOR_STATE.with(|state| {
+                state
+                    .borrow_mut()
+                    .history
+                    .push(Command::EditAnnotation { before, after });
+            });
+        }
+    }
+}
@@ -72,6 +72,22 @@ impl ColorSchemes {
         self
     }
 
+    pub fn get_type_color(&self, scheme: &str, data_type: &str) -> Color {
+        let table = self
+            .schemes
+            .get(scheme)
+            .or_else(|| self.schemes.get("light"));
+
+        table
+            .and_then(|table| {
+                table
+                    .get(&format!("port_{}", data_type))
+                    .or_else(|| table.get("port_any"))
+            })
+            .copied()
+            .unwrap_or(Color::ORANGE)
+    }
+
     pub fn get_color(&self, scheme: &str, color: &str) -> Option<Color> {
         let table = match self.schemes.get(scheme) {
             Some(s) => s,
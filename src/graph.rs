@@ -0,0 +1,429 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    rc::Rc,
+};
+
+use crate::{
+    node::{Connection, ConnectionKey, Node},
+    objects::Object,
+    structs::Vector2,
+};
+
+const LAYER_GAP: f32 = 80.0;
+const NODE_GAP: f32 = 40.0;
+const ORDERING_SWEEPS: usize = 8;
+
+pub struct EvaluationGraph {
+    pub order: Vec<String>,
+    pub cyclic: Vec<String>,
+}
+
+impl EvaluationGraph {
+    pub fn build(
+        node_ids: impl IntoIterator<Item = String>,
+        edges: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        let mut in_degree: BTreeMap<String, usize> =
+            node_ids.into_iter().map(|id| (id, 0)).collect();
+        let mut dependents: HashMap<String, BTreeSet<String>> = HashMap::new();
+
+        for (from, to) in edges {
+            if from == to || !in_degree.contains_key(&from) || !in_degree.contains_key(&to) {
+                continue;
+            }
+            if dependents.entry(from).or_default().insert(to.clone()) {
+                *in_degree.get_mut(&to).unwrap() += 1;
+            }
+        }
+
+        /* Kahn; ties are broken by node id so every pass runs in the same order */
+        let mut ready: BTreeSet<String> = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| id.clone())
+            .collect();
+        let mut order = Vec::with_capacity(in_degree.len());
+
+        while let Some(id) = ready.pop_first() {
+            if let Some(next) = dependents.get(&id) {
+                for dependent in next {
+                    let degree = in_degree.get_mut(dependent).unwrap();
+                    *degree -= 1;
+                    if *degree == 0 {
+                        ready.insert(dependent.clone());
+                    }
+                }
+            }
+            order.push(id);
+        }
+
+        let cyclic = in_degree
+            .into_iter()
+            .filter(|(_, degree)| *degree > 0)
+            .map(|(id, _)| id)
+            .collect();
+
+        Self { order, cyclic }
+    }
+
+    pub fn from_editor(
+        objects: &HashMap<String, Rc<RefCell<dyn Object>>>,
+        connections: &HashMap<ConnectionKey, Connection>,
+    ) -> Self {
+        let node_ids = objects
+            .iter()
+            .filter(|(_, obj)| obj.borrow().as_any().is::<Node>())
+            .map(|(id, _)| id.clone());
+
+        Self::build(node_ids, edges(connections))
+    }
+}
+
+fn edges(connections: &HashMap<ConnectionKey, Connection>) -> Vec<(String, String)> {
+    connections
+        .values()
+        .map(|conn| {
+            (
+                conn.from.borrow().parent_id.clone(),
+                conn.to.borrow().parent_id.clone(),
+            )
+        })
+        .collect()
+}
+
+pub fn creates_cycle(
+    connections: &HashMap<ConnectionKey, Connection>,
+    from: &str,
+    to: &str,
+) -> bool {
+    closes_cycle(&edges(connections), from, to)
+}
+
+/// Whether an edge `from` -> `to` would close a cycle, given the existing `edges`.
+fn closes_cycle(edges: &[(String, String)], from: &str, to: &str) -> bool {
+    if from == to {
+        return true;
+    }
+
+    let mut stack = vec![to.to_string()];
+    let mut visited: BTreeSet<String> = BTreeSet::new();
+
+    while let Some(current) = stack.pop() {
+        if current == from {
+            return true;
+        }
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+        for (edge_from, edge_to) in edges {
+            if *edge_from == current {
+                stack.push(edge_to.clone());
+            }
+        }
+    }
+
+    false
+}
+
+pub struct LayoutNode {
+    pub id: String,
+    /// Size of the node's bounds, title included.
+    pub size: Vector2,
+    /// Current vertical position, used as the starting order within a layer.
+    pub y: f32,
+}
+
+/// Places nodes left to right in dependency layers and returns the top left corner of each
+/// node's bounds, relative to the top left of the arrangement.
+pub fn layered_layout(
+    nodes: &[LayoutNode],
+    edges: &[(String, String)],
+) -> HashMap<String, Vector2> {
+    let ids: BTreeSet<&String> = nodes.iter().map(|node| &node.id).collect();
+    let edges: Vec<&(String, String)> = edges
+        .iter()
+        .filter(|(from, to)| from != to && ids.contains(from) && ids.contains(to))
+        .collect();
+    let graph = EvaluationGraph::build(
+        nodes.iter().map(|node| node.id.clone()),
+        edges.iter().map(|edge| (*edge).clone()),
+    );
+
+    /* Longest path layering; nodes stuck in a cycle go right after what is known of their inputs */
+    let mut layer_of: HashMap<&str, usize> = HashMap::new();
+    for id in graph.order.iter().chain(graph.cyclic.iter()) {
+        let layer = edges
+            .iter()
+            .filter(|(_, to)| to == id)
+            .filter_map(|(from, _)| layer_of.get(from.as_str()))
+            .map(|layer| layer + 1)
+            .max()
+            .unwrap_or(0);
+        layer_of.insert(id.as_str(), layer);
+    }
+
+    let layer_count = layer_of.values().max().map_or(0, |max| max + 1);
+    let mut layers: Vec<Vec<&LayoutNode>> = vec![vec![]; layer_count];
+    for node in nodes {
+        layers[layer_of[node.id.as_str()]].push(node);
+    }
+    for layer in &mut layers {
+        layer.sort_by(|a, b| a.y.total_cmp(&b.y));
+    }
+
+    /* Barycenter ordering, sweeping right and then left, to reduce wire crossings */
+    for sweep in 0..ORDERING_SWEEPS {
+        let forward = sweep % 2 == 0;
+        let indices: Vec<usize> = if forward {
+            (1..layer_count).collect()
+        } else {
+            (0..layer_count.saturating_sub(1)).rev().collect()
+        };
+
+        for i in indices {
+            let reference = if forward { i - 1 } else { i + 1 };
+            let rank: HashMap<&str, usize> = layers[reference]
+                .iter()
+                .copied()
+                .enumerate()
+                .map(|(rank, node)| (node.id.as_str(), rank))
+                .collect();
+
+            let mut keyed: Vec<(f32, &LayoutNode)> = layers[i]
+                .iter()
+                .copied()
+                .enumerate()
+                .map(|(index, node)| {
+                    let neighbours: Vec<usize> = edges
+                        .iter()
+                        .filter_map(|(from, to)| {
+                            if *to == node.id {
+                                rank.get(from.as_str())
+                            } else if *from == node.id {
+                                rank.get(to.as_str())
+                            } else {
+                                None
+                            }
+                        })
+                        .copied()
+                        .collect();
+
+                    let key = if neighbours.is_empty() {
+                        index as f32
+                    } else {
+                        neighbours.iter().sum::<usize>() as f32 / neighbours.len() as f32
+                    };
+                    (key, node)
+                })
+                .collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            layers[i] = keyed.into_iter().map(|(_, node)| node).collect();
+        }
+    }
+
+    let widths: Vec<f32> = layers
+        .iter()
+        .map(|layer| layer.iter().map(|node| node.size.x).fold(0.0, f32::max))
+        .collect();
+    let heights: Vec<f32> = layers
+        .iter()
+        .map(|layer| {
+            layer.iter().map(|node| node.size.y).sum::<f32>()
+                + NODE_GAP * layer.len().saturating_sub(1) as f32
+        })
+        .collect();
+    let tallest = heights.iter().copied().fold(0.0, f32::max);
+
+    let mut positions = HashMap::new();
+    let mut x = 0.0;
+    for (i, layer) in layers.iter().enumerate() {
+        let mut y = (tallest - heights[i]) / 2.0;
+        for node in layer {
+            positions.insert(node.id.clone(), Vector2::new(x, y, None));
+            y += node.size.y + NODE_GAP;
+        }
+        x += widths[i] + LAYER_GAP;
+    }
+
+    positions
+}
+
+pub fn evaluate(
+    objects: &HashMap<String, Rc<RefCell<dyn Object>>>,
+    connections: &HashMap<ConnectionKey, Connection>,
+) {
+    let graph = EvaluationGraph::from_editor(objects, connections);
+
+    if !graph.cyclic.is_empty() {
+        eprintln!(
+            "Döngüsel bağlantılar nedeniyle değerlendirilmeyen node'lar: {:?}",
+            graph.cyclic
+        );
+    }
+
+    for id in &graph.order {
+        for conn in connections.values() {
+            if conn.to.borrow().parent_id == *id {
+                conn.propagate();
+            }
+        }
+
+        if let Some(obj) = objects.get(id) {
+            let mut obj_mut = obj.borrow_mut();
+            if let Some(node) = obj_mut.as_any_mut().downcast_mut::<Node>() {
+                node.evaluate();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn edge_list(edges: &[(&str, &str)]) -> Vec<(String, String)> {
+        edges
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn build_orders_dependencies_first() {
+        let graph = EvaluationGraph::build(
+            ids(&["c", "b", "a", "d"]),
+            edge_list(&[("a", "b"), ("b", "c"), ("a", "c"), ("d", "c")]),
+        );
+
+        assert_eq!(graph.order, ids(&["a", "b", "d", "c"]));
+        assert!(graph.cyclic.is_empty());
+    }
+
+    #[test]
+    fn build_breaks_ties_by_id() {
+        let graph = EvaluationGraph::build(ids(&["z", "m", "a"]), vec![]);
+
+        assert_eq!(graph.order, ids(&["a", "m", "z"]));
+    }
+
+    #[test]
+    fn build_ignores_duplicate_self_and_unknown_edges() {
+        let graph = EvaluationGraph::build(
+            ids(&["a", "b"]),
+            edge_list(&[("a", "b"), ("a", "b"), ("b", "b"), ("x", "a"), ("b", "y")]),
+        );
+
+        assert_eq!(graph.order, ids(&["a", "b"]));
+        assert!(graph.cyclic.is_empty());
+    }
+
+    #[test]
+    fn build_reports_nodes_on_and_after_a_cycle() {
+        let graph = EvaluationGraph::build(
+            ids(&["a", "b", "c", "d"]),
+            edge_list(&[("a", "b"), ("b", "c"), ("c", "b"), ("c", "d")]),
+        );
+
+        assert_eq!(graph.order, ids(&["a"]));
+        assert_eq!(graph.cyclic, ids(&["b", "c", "d"]));
+    }
+
+    #[test]
+    fn closes_cycle_detects_paths_back_to_the_source() {
+        let edges = edge_list(&[("a", "b"), ("b", "c")]);
+
+        assert!(closes_cycle(&edges, "c", "a"));
+        assert!(closes_cycle(&edges, "b", "a"));
+        assert!(closes_cycle(&edges, "a", "a"));
+        assert!(!closes_cycle(&edges, "a", "c"));
+        assert!(!closes_cycle(&edges, "c", "d"));
+    }
+
+    #[test]
+    fn closes_cycle_terminates_on_existing_cycles() {
+        let edges = edge_list(&[("a", "b"), ("b", "a")]);
+
+        assert!(!closes_cycle(&edges, "c", "a"));
+    }
+
+    fn layout_node(id: &str, width: f32, height: f32, y: f32) -> LayoutNode {
+        LayoutNode {
+            id: id.to_string(),
+            size: Vector2::new(width, height, None),
+            y,
+        }
+    }
+
+    fn corner(positions: &HashMap<String, Vector2>, id: &str) -> (f32, f32) {
+        (positions[id].x, positions[id].y)
+    }
+
+    #[test]
+    fn layered_layout_places_layers_by_longest_path() {
+        let nodes = [
+            layout_node("a", 100.0, 50.0, 0.0),
+            layout_node("b", 200.0, 50.0, 0.0),
+            layout_node("c", 100.0, 50.0, 0.0),
+        ];
+        let positions = layered_layout(&nodes, &edge_list(&[("a", "b"), ("b", "c"), ("a", "c")]));
+
+        assert_eq!(corner(&positions, "a"), (0.0, 0.0));
+        assert_eq!(corner(&positions, "b"), (100.0 + LAYER_GAP, 0.0));
+        assert_eq!(corner(&positions, "c"), (300.0 + 2.0 * LAYER_GAP, 0.0));
+    }
+
+    #[test]
+    fn layered_layout_stacks_and_centres_layers() {
+        let nodes = [
+            layout_node("a", 100.0, 40.0, 0.0),
+            layout_node("b", 100.0, 60.0, 10.0),
+            layout_node("c", 100.0, 20.0, 0.0),
+        ];
+        let positions = layered_layout(&nodes, &edge_list(&[("a", "c"), ("b", "c")]));
+
+        let column_height = 100.0 + NODE_GAP;
+        assert_eq!(corner(&positions, "a"), (0.0, 0.0));
+        assert_eq!(corner(&positions, "b"), (0.0, 40.0 + NODE_GAP));
+        assert_eq!(
+            corner(&positions, "c"),
+            (100.0 + LAYER_GAP, (column_height - 20.0) / 2.0)
+        );
+    }
+
+    #[test]
+    fn layered_layout_orders_layers_to_uncross_wires() {
+        let nodes = [
+            layout_node("a", 100.0, 50.0, 0.0),
+            layout_node("b", 100.0, 50.0, 100.0),
+            layout_node("c", 100.0, 50.0, 0.0),
+            layout_node("d", 100.0, 50.0, 100.0),
+        ];
+        let positions = layered_layout(&nodes, &edge_list(&[("a", "d"), ("b", "c")]));
+
+        assert!(positions["a"].y < positions["b"].y);
+        assert!(positions["d"].y < positions["c"].y);
+    }
+
+    #[test]
+    fn layered_layout_places_every_node_despite_cycles_and_foreign_edges() {
+        let nodes = [
+            layout_node("a", 100.0, 50.0, 0.0),
+            layout_node("b", 100.0, 50.0, 0.0),
+            layout_node("c", 100.0, 50.0, 0.0),
+        ];
+        let positions = layered_layout(
+            &nodes,
+            &edge_list(&[("a", "b"), ("b", "c"), ("c", "b"), ("x", "a"), ("a", "a")]),
+        );
+
+        assert_eq!(positions.len(), 3);
+        assert_eq!(positions["a"].x, 0.0);
+        assert!(positions["b"].x > positions["a"].x);
+        assert!(positions["c"].x > positions["a"].x);
+    }
+}
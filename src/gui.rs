@@ -298,6 +298,8 @@ impl NodeSelector {
 
 pub struct ToolBarItem {
     pub label: String,
+    /// Shown as is instead of the `toolbar.*` translation, e.g. for file paths.
+    pub text: Option<String>,
     pub on_click: Option<String>,
     pub children: Vec<ToolBarItem>,
     pub expanded: bool,
@@ -329,14 +331,13 @@ impl Object for ToolBar {
 
         let mut x_offset = 10.0 + self.position.x;
         for item in &self.items {
-            let translation = self.translations.borrow().get_gui_translation(
-                &self.settings.borrow().language,
-                &format!("toolbar.{}", item.label),
-            );
+            let key = format!("toolbar.{}", item.label);
+            let translation = self.item_text(&key, item);
+            let text_width = self.font.borrow().measure_text(&translation, 20.0, 1.0).x;
             d.draw_rectangle(
                 x_offset as i32 - 5,
                 5,
-                (self.font.borrow().measure_text(&translation, 20.0, 1.0).x + 10.0) as i32,
+                (text_width + 10.0) as i32,
                 30,
                 self.colorscheme
                     .borrow()
@@ -363,38 +364,10 @@ impl Object for ToolBar {
             );
 
             if item.expanded {
-                let mut y_offset = 40.0;
-                d.draw_rectangle(
-                    x_offset as i32,
-                    y_offset as i32,
-                    self.get_largest_width(item) as i32 + 20,
-                    30 * item.children.len() as i32,
-                    self.colorscheme
-                        .borrow()
-                        .get_color(&self.settings.borrow().scheme, "topbar_button_background")
-                        .unwrap(),
-                );
-                for child in &item.children {
-                    let translation = self.translations.borrow().get_gui_translation(
-                        &self.settings.borrow().language,
-                        &format!("toolbar.{}.{}", item.label, child.label),
-                    );
-                    d.draw_text_ex(
-                        &*self.font.borrow(),
-                        &translation,
-                        Vector2::new(x_offset + 10.0, y_offset, None),
-                        20.0,
-                        1.0,
-                        self.colorscheme
-                            .borrow()
-                            .get_color(&self.settings.borrow().scheme, "topbar_foreground")
-                            .unwrap(),
-                    );
-                    y_offset += 30.0;
-                }
+                self.draw_menu(d, &key, item, x_offset, 40.0);
             }
 
-            x_offset += self.font.borrow().measure_text(&item.label, 20.0, 1.0).x + 20.0;
+            x_offset += text_width + 20.0;
         }
     }
 
@@ -403,12 +376,11 @@ impl Object for ToolBar {
 
         if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
             let mut x_offset = 10.0;
+            let mut items = std::mem::take(&mut self.items);
 
-            for item in &mut self.items {
-                let translation = self.translations.borrow().get_gui_translation(
-                    &self.settings.borrow().language,
-                    &format!("toolbar.{}", item.label),
-                );
+            for item in &mut items {
+                let key = format!("toolbar.{}", item.label);
+                let translation = self.item_text(&key, item);
                 let text_width = self.font.borrow().measure_text(&translation, 20.0, 1.0).x;
                 let text_rect = Rectangle::new(
                     x_offset + self.position.x - 5.0,
@@ -424,33 +396,22 @@ impl Object for ToolBar {
                     if let Some(event) = &item.on_click {
                         self.events.push(event.clone());
                     }
-                }
-
-                if item.expanded {
-                    let mut y_offset = 40.0;
-                    for child in &mut item.children {
-                        let translation = self.translations.borrow().get_gui_translation(
-                            &self.settings.borrow().language,
-                            &format!("toolbar.{}.{}", item.label, child.label),
-                        );
-                        let child_rect = Rectangle::new(
-                            x_offset + self.position.x,
-                            y_offset + self.position.y,
-                            self.font.borrow().measure_text(&translation, 20.0, 1.0).x + 20.0,
-                            30.0,
-                        );
-                        if child_rect.check_collision_point_rec(mouse_pos.clone()) {
-                            if let Some(event) = &child.on_click {
-                                self.events.push(event.clone());
-                            }
-                            item.expanded = false;
-                        }
-                        y_offset += 30.0;
-                    }
+                } else if item.expanded
+                    && self.click_menu(
+                        &key,
+                        item,
+                        x_offset + self.position.x,
+                        40.0 + self.position.y,
+                        mouse_pos.into(),
+                    )
+                {
+                    item.expanded = false;
                 }
 
                 x_offset += text_width + 20.0;
             }
+
+            self.items = items;
         }
     }
 
@@ -488,16 +449,286 @@ impl ToolBar {
         self.items.push(item);
     }
 
-    fn get_largest_width(&self, item: &ToolBarItem) -> f32 {
+    pub fn find_item(&mut self, label: &str) -> Option<&mut ToolBarItem> {
+        fn find<'a>(items: &'a mut [ToolBarItem], label: &str) -> Option<&'a mut ToolBarItem> {
+            for item in items {
+                if item.label == label {
+                    return Some(item);
+                }
+                if let Some(found) = find(&mut item.children, label) {
+                    return Some(found);
+                }
+            }
+            None
+        }
+
+        find(&mut self.items, label)
+    }
+
+    fn item_text(&self, key: &str, item: &ToolBarItem) -> String {
+        item.text.clone().unwrap_or_else(|| {
+            self.translations
+                .borrow()
+                .get_gui_translation(&self.settings.borrow().language, key)
+        })
+    }
+
+    fn get_largest_width(&self, key: &str, item: &ToolBarItem) -> f32 {
         let mut max_x: f32 = 0.0;
         for child in &item.children {
-            let translation = self.translations.borrow().get_gui_translation(
-                &self.settings.borrow().language,
-                &format!("toolbar.{}.{}", item.label, child.label),
-            );
+            let translation = self.item_text(&format!("{}.{}", key, child.label), child);
             max_x = max_x.max(self.font.borrow().measure_text(&translation, 20.0, 1.0).x);
         }
-        max_x
+        max_x + 20.0
+    }
+
+    fn draw_menu(&self, d: &mut RaylibDrawHandle, key: &str, item: &ToolBarItem, x: f32, y: f32) {
+        let width = self.get_largest_width(key, item);
+        let foreground = self
+            .colorscheme
+            .borrow()
+            .get_color(&self.settings.borrow().scheme, "topbar_foreground")
+            .unwrap();
+
+        d.draw_rectangle(
+            x as i32,
+            y as i32,
+            width as i32,
+            30 * item.children.len() as i32,
+            self.colorscheme
+                .borrow()
+                .get_color(&self.settings.borrow().scheme, "topbar_button_background")
+                .unwrap(),
+        );
+
+        let mut y_offset = y;
+        for child in &item.children {
+            let child_key = format!("{}.{}", key, child.label);
+            d.draw_text_ex(
+                &*self.font.borrow(),
+                &self.item_text(&child_key, child),
+                Vector2::new(x + 10.0, y_offset, None),
+                20.0,
+                1.0,
+                foreground,
+            );
+
+            if !child.children.is_empty() {
+                d.draw_text_ex(
+                    &*self.font.borrow(),
+                    ">",
+                    Vector2::new(x + width - 15.0, y_offset, None),
+                    20.0,
+                    1.0,
+                    foreground,
+                );
+                if child.expanded {
+                    self.draw_menu(d, &child_key, child, x + width, y_offset);
+                }
+            }
+
+            y_offset += 30.0;
+        }
+    }
+
+    /// Returns true when an entry was chosen and the menu should close.
+    fn click_menu(
+        &mut self,
+        key: &str,
+        item: &mut ToolBarItem,
+        x: f32,
+        y: f32,
+        mouse_pos: Vector2,
+    ) -> bool {
+        let width = self.get_largest_width(key, item);
+        let mut y_offset = y;
+
+        for child in &mut item.children {
+            let child_key = format!("{}.{}", key, child.label);
+            let child_rect = Rectangle::new(x, y_offset, width, 30.0);
+
+            if child_rect.check_collision_point_rec(mouse_pos.clone()) {
+                if !child.children.is_empty() {
+                    child.expanded = !child.expanded;
+                    return false;
+                }
+                if let Some(event) = &child.on_click {
+                    self.events.push(event.clone());
+                }
+                return true;
+            }
+
+            if child.expanded
+                && self.click_menu(&child_key, child, x + width, y_offset, mouse_pos.clone())
+            {
+                child.expanded = false;
+                return true;
+            }
+
+            y_offset += 30.0;
+        }
+
+        false
+    }
+}
+
+const CONTEXT_ROW_HEIGHT: f32 = 30.0;
+
+/// Popup of actions opened with a right click, drawn like the `ToolBar` menus.
+pub struct ContextMenu {
+    pub colorscheme: Rc<RefCell<ColorSchemes>>,
+    pub settings: Rc<RefCell<Settings>>,
+    pub translations: Rc<RefCell<Translations>>,
+    pub font: Rc<RefCell<Font>>,
+    pub open: bool,
+    /// Top left corner on screen.
+    pub position: Vector2,
+    /// Entries translated as `context.<label>` unless they carry their own text.
+    pub items: Vec<ToolBarItem>,
+    /// `on_click` of the chosen entry, taken by the window.
+    pub events: Vec<String>,
+}
+
+impl Object for ContextMenu {
+    fn draw(&self, d: &mut RaylibDrawHandle, _camera: &Camera) {
+        if !self.open {
+            return;
+        }
+
+        let colorscheme = self.colorscheme.borrow();
+        let settings = self.settings.borrow();
+        let color = |key: &str| {
+            colorscheme
+                .get_color(&settings.scheme, key)
+                .unwrap_or(Color::MAGENTA)
+        };
+        let mouse = d.get_mouse_position();
+
+        d.draw_rectangle_rec(self.area(), color("topbar_button_background"));
+
+        for (row, item) in self.items.iter().enumerate() {
+            let rect = self.row_rect(row);
+            if rect.check_collision_point_rec(mouse) {
+                d.draw_rectangle_rec(rect, color("topbar_button_background_expanded"));
+            }
+            d.draw_text_ex(
+                &*self.font.borrow(),
+                &self.item_text(item),
+                Vector2::new(rect.x + 10.0, rect.y + 5.0, None),
+                20.0,
+                1.0,
+                color("topbar_foreground"),
+            );
+        }
+    }
+
+    fn update(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread, _camera: &Camera) {
+        if !self.open {
+            return;
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            self.open = false;
+        }
+
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            || rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
+        {
+            let mouse = rl.get_mouse_position();
+            let chosen = (0..self.items.len())
+                .find(|row| self.row_rect(*row).check_collision_point_rec(mouse))
+                .and_then(|row| self.items[row].on_click.clone());
+
+            if let Some(event) = chosen
+                && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            {
+                self.events.push(event);
+            }
+            self.open = false;
+        }
+    }
+
+    fn set_property(&mut self, _key: String, _value: Box<dyn Any>) {}
+    fn get_property(&self, _key: String) -> Box<dyn Any> {
+        Box::new(())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl ContextMenu {
+    pub fn new(
+        colorscheme: Rc<RefCell<ColorSchemes>>,
+        settings: Rc<RefCell<Settings>>,
+        font: Rc<RefCell<Font>>,
+        translations: Rc<RefCell<Translations>>,
+    ) -> Self {
+        Self {
+            colorscheme,
+            settings,
+            translations,
+            font,
+            open: false,
+            position: Vector2::zero(),
+            items: vec![],
+            events: vec![],
+        }
+    }
+
+    /// Opens at `position` on screen, kept inside a `screen` sized window.
+    pub fn show(&mut self, position: Vector2, items: Vec<ToolBarItem>, screen: Vector2) {
+        self.items = items;
+        let area = self.area();
+        self.position = Vector2::new(
+            position.x.min(screen.x - area.width).max(0.0),
+            position.y.min(screen.y - area.height).max(0.0),
+            None,
+        );
+        self.open = true;
+    }
+
+    fn item_text(&self, item: &ToolBarItem) -> String {
+        item.text.clone().unwrap_or_else(|| {
+            self.translations.borrow().get_gui_translation(
+                &self.settings.borrow().language,
+                &format!("context.{}", item.label),
+            )
+        })
+    }
+
+    fn area(&self) -> Rectangle {
+        let width = self
+            .items
+            .iter()
+            .map(|item| {
+                self.font
+                    .borrow()
+                    .measure_text(&self.item_text(item), 20.0, 1.0)
+                    .x
+            })
+            .fold(0.0, f32::max)
+            + 20.0;
+
+        Rectangle::new(
+            self.position.x,
+            self.position.y,
+            width,
+            CONTEXT_ROW_HEIGHT * self.items.len() as f32,
+        )
+    }
+
+    fn row_rect(&self, row: usize) -> Rectangle {
+        let area = self.area();
+        Rectangle::new(
+            area.x,
+            area.y + row as f32 * CONTEXT_ROW_HEIGHT,
+            area.width,
+            CONTEXT_ROW_HEIGHT,
+        )
     }
 }
 
@@ -563,6 +794,7 @@ pub enum DialogButton {
 
 pub struct Dialog {
     pub type_name: String,
+    pub detail: Option<String>,
     pub buttons: Vec<(DialogButton, String)>,
     pub colorscheme: Rc<RefCell<ColorSchemes>>,
     pub translations: Rc<RefCell<Translations>>,
@@ -623,10 +855,13 @@ impl Dialog {
             1.0,
             frgcolor,
         );
-        let content = self.translations.borrow().get_gui_translation(
+        let mut content = self.translations.borrow().get_gui_translation(
             &self.settings.borrow().language,
             &format!("dialog.{}.content", self.type_name),
         );
+        if let Some(detail) = &self.detail {
+            content = format!("{} {}", content, detail);
+        }
         draw_text_wordwrap(
             d,
             &content,
@@ -735,6 +970,7 @@ impl Dialog {
     ) -> Self {
         Self {
             type_name,
+            detail: None,
             buttons,
             colorscheme,
             settings,
@@ -743,3 +979,575 @@ impl Dialog {
         }
     }
 }
+
+const MINIMAP_SIZE: f32 = 180.0;
+const MINIMAP_MARGIN: f32 = 10.0;
+
+/// Overview of the whole grid in the bottom right corner; clicking or dragging on it moves the camera.
+pub struct Minimap {
+    pub colorscheme: Rc<RefCell<ColorSchemes>>,
+    pub settings: Rc<RefCell<Settings>>,
+    /// World-space node bounds, refreshed by the window every frame.
+    pub nodes: Vec<Rectangle>,
+    /// World-space wire endpoints, refreshed by the window every frame.
+    pub wires: Vec<(Vector2, Vector2)>,
+    pub dragging: bool,
+    /// Camera target picked on the map, taken by the window.
+    pub jump_to: Option<Vector2>,
+}
+
+impl Object for Minimap {
+    fn draw(&self, d: &mut RaylibDrawHandle, camera: &Camera) {
+        let screen = Vector2::new(
+            d.get_screen_width() as f32,
+            d.get_screen_height() as f32,
+            None,
+        );
+        let area = self.area(screen.clone());
+        let colorscheme = self.colorscheme.borrow();
+        let settings = self.settings.borrow();
+        let scheme = &settings.scheme;
+        let color = |key: &str| colorscheme.get_color(scheme, key).unwrap_or(Color::MAGENTA);
+
+        d.draw_rectangle_rec(area, color("minimap_background"));
+
+        for (from, to) in &self.wires {
+            d.draw_line_ex(
+                self.to_map(area, from.clone()),
+                self.to_map(area, to.clone()),
+                1.0,
+                color("connection"),
+            );
+        }
+
+        for node in &self.nodes {
+            let corner = self.to_map(area, Vector2::new(node.x, node.y, None));
+            let scale = area.width / self.world().width;
+            d.draw_rectangle_rec(
+                Rectangle::new(
+                    corner.x,
+                    corner.y,
+                    (node.width * scale).max(2.0),
+                    (node.height * scale).max(2.0),
+                ),
+                color("minimap_node"),
+            );
+        }
+
+        let view_size = screen / camera.zoom;
+        let view_corner = camera.target.clone() - camera.offset.clone() / camera.zoom;
+        let corner = self.to_map(area, view_corner.clone());
+        let far = self.to_map(area, view_corner + view_size);
+        let viewport = Rectangle::new(corner.x, corner.y, far.x - corner.x, far.y - corner.y);
+        if let Some(viewport) = area.get_collision_rec(&viewport) {
+            d.draw_rectangle_lines_ex(viewport, 1.0, color("minimap_viewport"));
+        }
+
+        d.draw_rectangle_lines_ex(area, 1.0, color("minimap_border"));
+    }
+
+    fn update(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread, _camera: &Camera) {
+        let area = self.area(Vector2::new(
+            rl.get_screen_width() as f32,
+            rl.get_screen_height() as f32,
+            None,
+        ));
+        let mouse: Vector2 = rl.get_mouse_position().into();
+
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            && area.check_collision_point_rec(mouse.clone())
+        {
+            self.dragging = true;
+        }
+        if !rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+            self.dragging = false;
+        }
+
+        if self.dragging {
+            let world = self.world();
+            let x = ((mouse.x - area.x) / area.width).clamp(0.0, 1.0);
+            let y = ((mouse.y - area.y) / area.height).clamp(0.0, 1.0);
+            self.jump_to = Some(Vector2::new(
+                world.x + x * world.width,
+                world.y + y * world.height,
+                None,
+            ));
+        }
+    }
+
+    fn set_property(&mut self, _key: String, _value: Box<dyn Any>) {}
+    fn get_property(&self, _key: String) -> Box<dyn Any> {
+        Box::new(())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Minimap {
+    pub fn new(colorscheme: Rc<RefCell<ColorSchemes>>, settings: Rc<RefCell<Settings>>) -> Self {
+        Self {
+            colorscheme,
+            settings,
+            nodes: vec![],
+            wires: vec![],
+            dragging: false,
+            jump_to: None,
+        }
+    }
+
+    /// Screen rectangle of the map, keeping the grid's aspect ratio.
+    pub fn area(&self, screen: Vector2) -> Rectangle {
+        let world = self.world();
+        let (width, height) = if world.width >= world.height {
+            (MINIMAP_SIZE, MINIMAP_SIZE * world.height / world.width)
+        } else {
+            (MINIMAP_SIZE * world.width / world.height, MINIMAP_SIZE)
+        };
+
+        Rectangle::new(
+            screen.x - width - MINIMAP_MARGIN,
+            screen.y - height - MINIMAP_MARGIN,
+            width,
+            height,
+        )
+    }
+
+    /// The world covered by the grid, centered on the origin like `Window::get_grid`.
+    fn world(&self) -> Rectangle {
+        let settings = self.settings.borrow();
+        let width = settings.grid_size[0] * settings.grid_square_size[0];
+        let height = settings.grid_size[1] * settings.grid_square_size[1];
+
+        Rectangle::new(-width / 2.0, -height / 2.0, width, height)
+    }
+
+    fn to_map(&self, area: Rectangle, point: Vector2) -> Vector2 {
+        let world = self.world();
+        Vector2::new(
+            area.x + (point.x - world.x) / world.width * area.width,
+            area.y + (point.y - world.y) / world.height * area.height,
+            None,
+        )
+    }
+}
+
+const PALETTE_WIDTH: f32 = 340.0;
+const PALETTE_ROW_HEIGHT: f32 = 26.0;
+const PALETTE_ROWS: usize = 8;
+
+/// Space/Tab quick-add popup that fuzzy-searches every node type.
+pub struct NodePalette {
+    pub libary: Rc<RefCell<ModuleManager>>,
+    pub colorscheme: Rc<RefCell<ColorSchemes>>,
+    pub settings: Rc<RefCell<Settings>>,
+    pub translations: Rc<RefCell<Translations>>,
+    pub font: Rc<RefCell<Font>>,
+    pub open: bool,
+    pub position: Vector2,
+    /// Where the chosen node is placed, the mouse position when the palette was opened.
+    pub world_position: Vector2,
+    pub query: String,
+    pub highlighted: usize,
+    /// Type id, translated title and description of every node type.
+    entries: Vec<(String, String, String)>,
+    results: Vec<usize>,
+    /// Type id picked by the user, taken by the window.
+    pub chosen: Option<String>,
+}
+
+impl Object for NodePalette {
+    fn draw(&self, d: &mut RaylibDrawHandle, _camera: &Camera) {
+        if !self.open {
+            return;
+        }
+
+        let colorscheme = self.colorscheme.borrow();
+        let settings = self.settings.borrow();
+        let color = |key: &str| {
+            colorscheme
+                .get_color(&settings.scheme, key)
+                .unwrap_or(Color::MAGENTA)
+        };
+        let font = self.font.borrow();
+        let area = self.area();
+
+        d.draw_rectangle_rec(area, color("palette_background"));
+
+        let (query, query_color) = if self.query.is_empty() {
+            (
+                self.translations
+                    .borrow()
+                    .get_gui_translation(&settings.language, "palette.placeholder"),
+                color("palette_hint"),
+            )
+        } else {
+            (format!("{}_", self.query), color("palette_foreground"))
+        };
+        d.draw_text_ex(
+            &*font,
+            &query,
+            Vector2::new(area.x + 8.0, area.y + 5.0, None),
+            20.0,
+            1.0,
+            query_color,
+        );
+        d.draw_line_ex(
+            Vector2::new(area.x, area.y + PALETTE_ROW_HEIGHT + 2.0, None),
+            Vector2::new(area.x + area.width, area.y + PALETTE_ROW_HEIGHT + 2.0, None),
+            1.0,
+            color("palette_border"),
+        );
+
+        if self.results.is_empty() {
+            d.draw_text_ex(
+                &*font,
+                &self
+                    .translations
+                    .borrow()
+                    .get_gui_translation(&settings.language, "palette.empty"),
+                Vector2::new(area.x + 8.0, area.y + PALETTE_ROW_HEIGHT + 8.0, None),
+                18.0,
+                1.0,
+                color("palette_hint"),
+            );
+        }
+
+        for (row, index) in self.results.iter().take(PALETTE_ROWS).enumerate() {
+            let (type_name, title, _) = &self.entries[*index];
+            let rect = self.row_rect(row);
+
+            if row == self.highlighted {
+                d.draw_rectangle_rec(rect, color("palette_highlight"));
+            }
+
+            d.draw_text_ex(
+                &*font,
+                title,
+                Vector2::new(rect.x + 8.0, rect.y + 4.0, None),
+                18.0,
+                1.0,
+                color("palette_foreground"),
+            );
+            let id_width = font.measure_text(type_name, 14.0, 1.0).x;
+            d.draw_text_ex(
+                &*font,
+                type_name,
+                Vector2::new(rect.x + rect.width - id_width - 8.0, rect.y + 7.0, None),
+                14.0,
+                1.0,
+                color("palette_hint"),
+            );
+        }
+
+        d.draw_rectangle_lines_ex(area, 1.0, color("palette_border"));
+    }
+
+    fn update(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread, _camera: &Camera) {
+        if !self.open {
+            return;
+        }
+
+        let mut changed = false;
+        while let Some(key) = rl.get_key_pressed() {
+            match key {
+                KeyboardKey::KEY_ESCAPE => self.open = false,
+                KeyboardKey::KEY_BACKSPACE => changed |= self.query.pop().is_some(),
+                KeyboardKey::KEY_UP => self.highlighted = self.highlighted.saturating_sub(1),
+                KeyboardKey::KEY_DOWN | KeyboardKey::KEY_TAB => {
+                    let last = self.results.len().min(PALETTE_ROWS).saturating_sub(1);
+                    self.highlighted = (self.highlighted + 1).min(last);
+                }
+                KeyboardKey::KEY_ENTER | KeyboardKey::KEY_KP_ENTER => self.choose(self.highlighted),
+                _ => {}
+            }
+        }
+
+        while let Some(ch) = rl.get_char_pressed() {
+            if !ch.is_control() {
+                self.query.push(ch);
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.refresh();
+        }
+
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            let mouse = rl.get_mouse_position();
+            let row = (0..self.results.len().min(PALETTE_ROWS))
+                .find(|row| self.row_rect(*row).check_collision_point_rec(mouse));
+
+            match row {
+                Some(row) => self.choose(row),
+                None if !self.area().check_collision_point_rec(mouse) => self.open = false,
+                None => {}
+            }
+        }
+    }
+
+    fn set_property(&mut self, _key: String, _value: Box<dyn Any>) {}
+    fn get_property(&self, _key: String) -> Box<dyn Any> {
+        Box::new(())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl NodePalette {
+    pub fn new(
+        libary: Rc<RefCell<ModuleManager>>,
+        font: Rc<RefCell<Font>>,
+        colorscheme: Rc<RefCell<ColorSchemes>>,
+        settings: Rc<RefCell<Settings>>,
+        translations: Rc<RefCell<Translations>>,
+    ) -> Self {
+        Self {
+            libary,
+            font,
+            colorscheme,
+            settings,
+            translations,
+            open: false,
+            position: Vector2::zero(),
+            world_position: Vector2::zero(),
+            query: String::new(),
+            highlighted: 0,
+            entries: vec![],
+            results: vec![],
+            chosen: None,
+        }
+    }
+
+    /// Opens at `position` on screen, kept inside a `screen` sized window, listing the node types
+    /// accepted by `filter`.
+    pub fn show(
+        &mut self,
+        position: Vector2,
+        world_position: Vector2,
+        screen: Vector2,
+        filter: impl Fn(&str) -> bool,
+    ) {
+        self.entries = palette_entries(
+            self.libary.borrow().type_names(),
+            filter,
+            &self.translations.borrow(),
+            &self.settings.borrow().language,
+        );
+
+        let height = PALETTE_ROW_HEIGHT * (PALETTE_ROWS + 1) as f32 + 8.0;
+        self.position = Vector2::new(
+            position.x.min(screen.x - PALETTE_WIDTH).max(0.0),
+            position.y.min(screen.y - height).max(0.0),
+            None,
+        );
+        self.world_position = world_position;
+        self.query.clear();
+        self.open = true;
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        self.results = rank_entries(&self.entries, &self.query);
+        self.highlighted = 0;
+    }
+
+    fn choose(&mut self, row: usize) {
+        if let Some(index) = self.results.get(row) {
+            self.chosen = Some(self.entries[*index].0.clone());
+            self.open = false;
+        }
+    }
+
+    fn area(&self) -> Rectangle {
+        let rows = self.results.len().clamp(1, PALETTE_ROWS);
+        Rectangle::new(
+            self.position.x,
+            self.position.y,
+            PALETTE_WIDTH,
+            PALETTE_ROW_HEIGHT * (rows + 1) as f32 + 8.0,
+        )
+    }
+
+    fn row_rect(&self, row: usize) -> Rectangle {
+        Rectangle::new(
+            self.position.x,
+            self.position.y + PALETTE_ROW_HEIGHT * (row + 1) as f32 + 4.0,
+            PALETTE_WIDTH,
+            PALETTE_ROW_HEIGHT,
+        )
+    }
+}
+
+/// Palette rows for the registry keys in `type_names` that `filter` accepts, with their
+/// translated title and description.
+fn palette_entries(
+    type_names: Vec<String>,
+    filter: impl Fn(&str) -> bool,
+    translations: &Translations,
+    language: &str,
+) -> Vec<(String, String, String)> {
+    type_names
+        .into_iter()
+        .filter(|type_name| filter(type_name))
+        .map(|type_name| {
+            let text = translations.get_node_translation(language, &type_name);
+            (type_name, text.title, text.description)
+        })
+        .collect()
+}
+
+/// Indices of the `entries` matching `query`, best match first.
+fn rank_entries(entries: &[(String, String, String)], query: &str) -> Vec<usize> {
+    let mut scored: Vec<(i32, usize)> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (type_name, title, description))| {
+            let score = [
+                fuzzy_score(query, type_name),
+                fuzzy_score(query, title),
+                fuzzy_score(query, description).map(|score| score / 2),
+            ]
+            .into_iter()
+            .flatten()
+            .max()?;
+            Some((score, index))
+        })
+        .collect();
+    scored.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then_with(|| entries[*a].1.cmp(&entries[*b].1))
+    });
+
+    scored.into_iter().map(|(_, index)| index).collect()
+}
+
+/// Scores `text` when every character of `query` appears in it in order; runs and word starts
+/// score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut start = 0;
+    let mut previous: Option<usize> = None;
+
+    for ch in query.to_lowercase().chars() {
+        let found = start + text[start..].iter().position(|c| *c == ch)?;
+
+        score += match previous {
+            Some(previous) if found == previous + 1 => 5,
+            _ => 1,
+        };
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        previous = Some(found);
+        start = found + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_needs_every_character_in_order() {
+        assert!(fuzzy_score("ba", "abc").is_none());
+        assert!(fuzzy_score("add", "math.sub").is_none());
+        assert!(fuzzy_score("mad", "math.add").is_some());
+    }
+
+    #[test]
+    fn fuzzy_score_ignores_case() {
+        assert_eq!(
+            fuzzy_score("ADD", "math.add"),
+            fuzzy_score("add", "Math.Add")
+        );
+        assert!(fuzzy_score("ADD", "math.add").is_some());
+    }
+
+    #[test]
+    fn fuzzy_score_accepts_an_empty_query() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_runs() {
+        assert_eq!(fuzzy_score("add", "add"), Some(14));
+        assert!(fuzzy_score("add", "add") > fuzzy_score("add", "axdxd"));
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts() {
+        assert!(fuzzy_score("s", "math.sub") > fuzzy_score("s", "gas"));
+    }
+
+    fn translations() -> Rc<RefCell<Translations>> {
+        let translations = Translations::new();
+        translations.borrow_mut().load_from_file(
+            r#"{"nodes": {
+                "doranode:math.add": {"title": "Addition", "description": "Adds input A to input B."},
+                "doranode:math.sub": {"title": "Subtraction", "description": "Subtracts input B from input A."}
+            }}"#
+                .to_string(),
+            "en",
+        );
+        translations
+    }
+
+    #[test]
+    fn palette_finds_nodes_by_their_translated_title() {
+        let entries = palette_entries(
+            vec![
+                "doranode:math.add".to_string(),
+                "doranode:math.sub".to_string(),
+            ],
+            |_| true,
+            &translations().borrow(),
+            "en",
+        );
+
+        assert_eq!(entries[0].1, "Addition");
+        assert_eq!(rank_entries(&entries, "subtraction"), vec![1]);
+        assert_eq!(rank_entries(&entries, "addit")[0], 0);
+    }
+
+    #[test]
+    fn palette_keys_resolve_port_signatures() {
+        let mut modules = ModuleManager::new();
+        let key = crate::modules::registry_key("doranode", "nodes.math_add");
+        modules.port_signatures.insert(
+            key.clone(),
+            vec![
+                ("A".to_string(), false, "number".to_string()),
+                ("A + B".to_string(), true, "number".to_string()),
+            ],
+        );
+
+        let entries = palette_entries(
+            vec![key],
+            |type_name| {
+                modules
+                    .ports(type_name)
+                    .is_some_and(|ports| ports.iter().any(|(_, is_output, _)| !is_output))
+            },
+            &translations().borrow(),
+            "en",
+        );
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "doranode:math.add");
+        assert_eq!(entries[0].1, "Addition");
+    }
+}
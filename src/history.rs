@@ -0,0 +1,389 @@
+use crate::{
+    node::{ComponentValues, ConnectionKey},
+    routing::WireStyle,
+    save::{AnnotationSave, NodeSave},
+    structs::Vector2,
+};
+
+const HISTORY_LIMIT: usize = 256;
+
+#[derive(Clone, Debug)]
+pub enum Command {
+    AddNodes {
+        nodes: Vec<NodeSave>,
+        connections: Vec<ConnectionKey>,
+    },
+    RemoveNodes {
+        nodes: Vec<NodeSave>,
+        connections: Vec<ConnectionKey>,
+    },
+    MoveNodes(Vec<(String, Vector2, Vector2)>),
+    AddConnection(ConnectionKey),
+    RemoveConnection(ConnectionKey),
+    EditComponents {
+        node_id: String,
+        before: ComponentValues,
+        after: ComponentValues,
+    },
+    AddAnnotation(AnnotationSave),
+    RemoveAnnotation(AnnotationSave),
+    EditAnnotation {
+        before: AnnotationSave,
+        after: AnnotationSave,
+    },
+    SetReroutes {
+        key: ConnectionKey,
+        before: Vec<Vector2>,
+        after: Vec<Vector2>,
+    },
+    SetWireStyle {
+        key: ConnectionKey,
+        before: Option<WireStyle>,
+        after: Option<WireStyle>,
+    },
+    /// Node ids with the bypass state they were switched to.
+    SetBypass(Vec<(String, bool)>),
+    /// Node ids with the collapsed state they were switched to.
+    SetCollapsed(Vec<(String, bool)>),
+    RenameNode {
+        id: String,
+        before: Option<String>,
+        after: Option<String>,
+    },
+    /// Node ids with their color tag before and after.
+    SetNodeColors(Vec<(String, Option<String>, Option<String>)>),
+    Group(Vec<Command>),
+}
+
+impl Command {
+    pub fn inverse(&self) -> Command {
+        match self {
+            Command::AddNodes { nodes, connections } => Command::RemoveNodes {
+                nodes: nodes.clone(),
+                connections: connections.clone(),
+            },
+            Command::RemoveNodes { nodes, connections } => Command::AddNodes {
+                nodes: nodes.clone(),
+                connections: connections.clone(),
+            },
+            Command::MoveNodes(moves) => Command::MoveNodes(
+                moves
+                    .iter()
+                    .map(|(id, from, to)| (id.clone(), to.clone(), from.clone()))
+                    .collect(),
+            ),
+            Command::AddConnection(name) => Command::RemoveConnection(name.clone()),
+            Command::RemoveConnection(name) => Command::AddConnection(name.clone()),
+            Command::EditComponents {
+                node_id,
+                before,
+                after,
+            } => Command::EditComponents {
+                node_id: node_id.clone(),
+                before: after.clone(),
+                after: before.clone(),
+            },
+            Command::AddAnnotation(save) => Command::RemoveAnnotation(save.clone()),
+            Command::RemoveAnnotation(save) => Command::AddAnnotation(save.clone()),
+            Command::EditAnnotation { before, after } => Command::EditAnnotation {
+                before: after.clone(),
+                after: before.clone(),
+            },
+            Command::SetReroutes { key, before, after } => Command::SetReroutes {
+                key: key.clone(),
+                before: after.clone(),
+                after: before.clone(),
+            },
+            Command::SetWireStyle { key, before, after } => Command::SetWireStyle {
+                key: key.clone(),
+                before: *after,
+                after: *before,
+            },
+            Command::SetBypass(nodes) => Command::SetBypass(
+                nodes
+                    .iter()
+                    .map(|(id, bypassed)| (id.clone(), !bypassed))
+                    .collect(),
+            ),
+            Command::SetCollapsed(nodes) => Command::SetCollapsed(
+                nodes
+                    .iter()
+                    .map(|(id, collapsed)| (id.clone(), !collapsed))
+                    .collect(),
+            ),
+            Command::RenameNode { id, before, after } => Command::RenameNode {
+                id: id.clone(),
+                before: after.clone(),
+                after: before.clone(),
+            },
+            Command::SetNodeColors(nodes) => Command::SetNodeColors(
+                nodes
+                    .iter()
+                    .map(|(id, before, after)| (id.clone(), after.clone(), before.clone()))
+                    .collect(),
+            ),
+            Command::Group(commands) => {
+                Command::Group(commands.iter().rev().map(Command::inverse).collect())
+            }
+        }
+    }
+}
+
+pub struct History {
+    undo_stack: Vec<Command>,
+    redo_stack: Vec<Command>,
+    sealed: bool,
+    changed: bool,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            undo_stack: vec![],
+            redo_stack: vec![],
+            sealed: true,
+            changed: false,
+        }
+    }
+
+    pub fn push(&mut self, command: Command) {
+        self.redo_stack.clear();
+        self.changed = true;
+
+        /* Typing into a TextBox or dragging a Slider is one step until the mouse is released */
+        if !self.sealed
+            && let (
+                Some(Command::EditComponents { node_id, after, .. }),
+                Command::EditComponents {
+                    node_id: new_id,
+                    after: new_after,
+                    ..
+                },
+            ) = (self.undo_stack.last_mut(), &command)
+            && *node_id == *new_id
+        {
+            *after = new_after.clone();
+            return;
+        }
+
+        self.sealed = false;
+        self.undo_stack.push(command);
+
+        if self.undo_stack.len() > HISTORY_LIMIT {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    pub fn undo(&mut self) -> Option<Command> {
+        let command = self.undo_stack.pop()?;
+        self.redo_stack.push(command.clone());
+        self.sealed = true;
+        self.changed = true;
+        Some(command.inverse())
+    }
+
+    pub fn redo(&mut self) -> Option<Command> {
+        let command = self.redo_stack.pop()?;
+        self.undo_stack.push(command.clone());
+        self.sealed = true;
+        self.changed = true;
+        Some(command)
+    }
+
+    /// Whether anything was done, undone or redone since the last call.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.sealed = true;
+        self.changed = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(from: &str, to: &str) -> ConnectionKey {
+        ConnectionKey {
+            from_node: from.to_string(),
+            from_port: "out".to_string(),
+            to_node: to.to_string(),
+            to_port: "in".to_string(),
+        }
+    }
+
+    fn edit(node_id: &str, before: i64, after: i64) -> Command {
+        let values = |value: i64| {
+            ComponentValues::from([(
+                "slider".to_string(),
+                [("value".to_string(), value.into())].into(),
+            )])
+        };
+        Command::EditComponents {
+            node_id: node_id.to_string(),
+            before: values(before),
+            after: values(after),
+        }
+    }
+
+    fn same(a: &Command, b: &Command) -> bool {
+        format!("{:?}", a) == format!("{:?}", b)
+    }
+
+    #[test]
+    fn inverse_swaps_additions_and_removals() {
+        let add = Command::AddConnection(key("a", "b"));
+        assert!(matches!(add.inverse(), Command::RemoveConnection(k) if k == key("a", "b")));
+
+        let remove = Command::RemoveNodes {
+            nodes: vec![],
+            connections: vec![key("a", "b")],
+        };
+        assert!(matches!(
+            remove.inverse(),
+            Command::AddNodes { connections, .. } if connections == vec![key("a", "b")]
+        ));
+    }
+
+    #[test]
+    fn inverse_swaps_before_and_after() {
+        let inverse = edit("n", 1, 2).inverse();
+        assert!(same(&inverse, &edit("n", 2, 1)));
+
+        let moves = Command::MoveNodes(vec![(
+            "n".to_string(),
+            Vector2::new(0.0, 0.0, None),
+            Vector2::new(10.0, 5.0, None),
+        )]);
+        assert!(matches!(
+            moves.inverse(),
+            Command::MoveNodes(m) if m[0].1 == Vector2::new(10.0, 5.0, None)
+                && m[0].2 == Vector2::zero()
+        ));
+    }
+
+    #[test]
+    fn inverse_of_inverse_is_the_command() {
+        let commands = [
+            edit("n", 1, 2),
+            Command::SetBypass(vec![("n".to_string(), true)]),
+            Command::SetWireStyle {
+                key: key("a", "b"),
+                before: None,
+                after: Some(WireStyle::Straight),
+            },
+            Command::RenameNode {
+                id: "n".to_string(),
+                before: None,
+                after: Some("Total".to_string()),
+            },
+        ];
+
+        for command in &commands {
+            assert!(same(&command.inverse().inverse(), command));
+        }
+    }
+
+    #[test]
+    fn inverse_of_group_runs_backwards() {
+        let group = Command::Group(vec![
+            Command::RemoveConnection(key("a", "b")),
+            Command::AddConnection(key("c", "b")),
+        ]);
+
+        let Command::Group(inverse) = group.inverse() else {
+            panic!("inverse of a group is not a group");
+        };
+        assert!(same(&inverse[0], &Command::RemoveConnection(key("c", "b"))));
+        assert!(same(&inverse[1], &Command::AddConnection(key("a", "b"))));
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_stacks() {
+        let mut history = History::new();
+        history.push(Command::AddConnection(key("a", "b")));
+        assert!(history.take_changed());
+        assert!(!history.take_changed());
+
+        let undone = history.undo().unwrap();
+        assert!(same(&undone, &Command::RemoveConnection(key("a", "b"))));
+        assert!(history.undo().is_none());
+
+        let redone = history.redo().unwrap();
+        assert!(same(&redone, &Command::AddConnection(key("a", "b"))));
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn push_clears_redo() {
+        let mut history = History::new();
+        history.push(Command::AddConnection(key("a", "b")));
+        history.undo();
+        history.push(Command::AddConnection(key("c", "d")));
+
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn unsealed_edits_of_one_node_merge() {
+        let mut history = History::new();
+        history.push(edit("n", 1, 2));
+        history.push(edit("n", 2, 3));
+        history.push(edit("n", 3, 4));
+
+        assert!(same(&history.undo().unwrap(), &edit("n", 4, 1)));
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn sealing_or_another_node_starts_a_new_step() {
+        let mut history = History::new();
+        history.push(edit("n", 1, 2));
+        history.seal();
+        history.push(edit("n", 2, 3));
+        history.push(edit("m", 1, 2));
+
+        assert!(same(&history.undo().unwrap(), &edit("m", 2, 1)));
+        assert!(same(&history.undo().unwrap(), &edit("n", 3, 2)));
+        assert!(same(&history.undo().unwrap(), &edit("n", 2, 1)));
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn oldest_steps_fall_off_past_the_limit() {
+        let mut history = History::new();
+        for i in 0..HISTORY_LIMIT + 10 {
+            history.push(Command::AddConnection(key(&i.to_string(), "sink")));
+        }
+
+        let mut undone = 0;
+        let mut last = None;
+        while let Some(command) = history.undo() {
+            undone += 1;
+            last = Some(command);
+        }
+        assert_eq!(undone, HISTORY_LIMIT);
+        assert!(same(
+            &last.unwrap(),
+            &Command::RemoveConnection(key("10", "sink"))
+        ));
+    }
+
+    #[test]
+    fn clear_forgets_everything() {
+        let mut history = History::new();
+        history.push(Command::AddConnection(key("a", "b")));
+        history.clear();
+
+        assert!(history.undo().is_none());
+        assert!(!history.take_changed());
+    }
+}
@@ -1,9 +1,14 @@
+pub mod annotation;
 pub mod colorscheme;
 pub mod draw;
+pub mod graph;
 pub mod gui;
+pub mod history;
 pub mod modules;
 pub mod node;
 pub mod objects;
+pub mod routing;
+pub mod runner;
 pub mod save;
 pub mod settings;
 pub mod structs;
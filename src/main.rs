@@ -1,18 +1,20 @@
-use std::{cell::RefCell, env, rc::Rc};
+use std::{cell::RefCell, env, process, rc::Rc};
 
 use pyo3::{PyResult, Python, prelude::*, pymodule, types::PyModule};
 
-use crate::{
-    save::SaveFile,
-    window::{EDITOR_STATE, Window},
-};
+use crate::window::{EDITOR_STATE, Window};
 
+mod annotation;
 mod colorscheme;
 mod draw;
+mod graph;
 mod gui;
+mod history;
 mod modules;
 mod node;
 mod objects;
+mod routing;
+mod runner;
 mod save;
 mod settings;
 mod structs;
@@ -24,14 +26,33 @@ fn main() {
         let venv_path = "venv";
         env::set_var("PYO3_PYTHON", format!("{}/bin/python", venv_path));
     };
-    let mut window = Window::new();
-    let (rl_handle, rl_thread) = window.init();
 
     let args: Vec<String> = env::args().collect();
 
+    if args.get(1).map(String::as_str) == Some("run") {
+        let Some(project) = args.get(2) else {
+            eprintln!("Usage: doranode-rs run <project.dnode> [--inputs <inputs.json>]");
+            process::exit(2);
+        };
+        let inputs = args
+            .iter()
+            .position(|arg| arg == "--inputs")
+            .and_then(|i| args.get(i + 1))
+            .map(String::as_str);
+
+        if let Err(e) = runner::run(project, inputs) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let mut window = Window::new();
+    let (rl_handle, rl_thread) = window.init();
+
     if args.len() == 2 {
         EDITOR_STATE.with_borrow_mut(|state| {
-            window.load_from_save(SaveFile::from_file(&args[1]).unwrap(), state);
+            window.open_project(state, Some(args[1].clone()));
         })
     }
 
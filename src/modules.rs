@@ -11,19 +11,35 @@ use pyo3::prelude::*;
 use pyo3::types::PyModule;
 
 use crate::colorscheme::ColorSchemes;
-use crate::node::{Node, PyNode};
+use crate::node::{Node, PyNode, format_py_error};
 use crate::settings::Settings;
 use crate::structs::Vector2;
 use crate::translations::Translations;
 
+/// Label, whether it is an output, and data type of one port.
+pub type PortSignature = (String, bool, String);
+
+/// Key a node file is registered, translated and cached under: `nodes.math_add` of the
+/// `doranode` module becomes `doranode:math.add`.
+pub fn registry_key(module: &str, node_module: &str) -> String {
+    format!(
+        "{}:{}",
+        module,
+        node_module.replace("_", ".").replace("nodes.", "")
+    )
+}
+
 pub struct ModuleManager {
     pub modules: HashMap<String, (Module, Py<PyModule>, HashMap<String, Py<PyAny>>)>,
+    /// Ports of every node type, read once when its module is added.
+    pub port_signatures: HashMap<String, Vec<PortSignature>>,
 }
 
 impl ModuleManager {
     pub fn new() -> Self {
         Self {
             modules: HashMap::new(),
+            port_signatures: HashMap::new(),
         }
     }
 
@@ -95,26 +111,42 @@ impl ModuleManager {
 
             for path in node_files {
                 let module_name = path.trim_end_matches(".py").replace("/", ".");
-                println!("{}", module_name);
-                let module = py.import(&module_name).expect("hata").into();
+                let module: Py<PyAny> = match py.import(&module_name) {
+                    Ok(module) => module.into(),
+                    Err(e) => {
+                        eprintln!("{}", format_py_error(py, &e));
+                        continue;
+                    }
+                };
 
                 self.modules
                     .get_mut(&config.module.name.clone())
                     .unwrap()
                     .2
-                    .insert(
-                        format!(
-                            "{}:{}",
-                            config.module.name,
-                            module_name.replace("_", ".").replace("nodes.", "")
-                        ),
-                        module,
-                    );
+                    .insert(registry_key(&config.module.name, &module_name), module);
             }
 
             Ok(())
         });
 
+        let type_names: Vec<String> = self
+            .modules
+            .get(&config.module.name)
+            .map(|(_, _, nodes)| nodes.keys().cloned().collect())
+            .unwrap_or_default();
+        Python::attach(|py| {
+            for type_name in type_names {
+                if let Some(py_node) = self.generate_py_node(py, &type_name) {
+                    let ports = py_node
+                        .ports
+                        .into_iter()
+                        .map(|(label, is_output, _, data_type)| (label, is_output, data_type))
+                        .collect();
+                    self.port_signatures.insert(type_name, ports);
+                }
+            }
+        });
+
         Some(self)
     }
 
@@ -122,45 +154,77 @@ impl ModuleManager {
         &self,
         position: Vector2,
         type_name: String,
-        font: Rc<RefCell<Font>>,
+        font: Option<Rc<RefCell<Font>>>,
         translations: Rc<RefCell<Translations>>,
         color_schemes: Rc<RefCell<ColorSchemes>>,
         settings: Rc<RefCell<Settings>>,
         id: String,
     ) -> Option<Rc<RefCell<Node>>> {
         Python::attach(|py| -> Option<Rc<RefCell<Node>>> {
-            let py_func = self
-                .modules
-                .iter()
-                .find(|(name, _)| type_name.starts_with(*name))?
-                .1
-                .2
-                .iter()
-                .find(|(name, _)| type_name.ends_with(*name))?
-                .1
-                .getattr(py, "generate")
-                .ok()?;
-
-            println!("ok");
-
-            Some(
-                py_func
-                    .call(py, (), None)
-                    .expect("call")
-                    .extract::<PyNode>(py)
-                    .expect("extract")
-                    .to_node(
-                        position.clone(),
-                        font.clone(),
-                        translations.clone(),
-                        color_schemes.clone(),
-                        settings.clone(),
-                        id.clone(),
-                    ),
-            )
+            let py_node = self.generate_py_node(py, &type_name)?;
+
+            let node = py_node.to_node(
+                position.clone(),
+                font.clone(),
+                translations.clone(),
+                color_schemes.clone(),
+                settings.clone(),
+                id.clone(),
+            );
+
+            // Modules may leave the prefix blank (" :math.add"); the registry key is the real type.
+            node.borrow_mut().type_name = type_name.clone();
+
+            Some(node)
         })
     }
 
+    /// Ports of `type_name`, `None` if its module is not loaded or its generate function failed.
+    pub fn ports(&self, type_name: &str) -> Option<&[PortSignature]> {
+        self.port_signatures.get(type_name).map(Vec::as_slice)
+    }
+
+    fn generate_py_node(&self, py: Python, type_name: &str) -> Option<PyNode> {
+        let py_func = self
+            .modules
+            .iter()
+            .find(|(name, _)| type_name.starts_with(*name))?
+            .1
+            .2
+            .iter()
+            .find(|(name, _)| type_name.ends_with(*name))?
+            .1
+            .getattr(py, "generate")
+            .ok()?;
+
+        let py_node = py_func
+            .call(py, (), None)
+            .and_then(|node| node.extract::<PyNode>(py).map_err(Into::into));
+
+        match py_node {
+            Ok(py_node) => Some(py_node),
+            Err(e) => {
+                eprintln!(
+                    "Generate function of {} failed:\n{}",
+                    type_name,
+                    format_py_error(py, &e)
+                );
+                None
+            }
+        }
+    }
+
+    /// Registry key of every loaded node type, which is also its translation key.
+    pub fn type_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .modules
+            .values()
+            .flat_map(|(_, _, nodes)| nodes.keys().cloned())
+            .collect();
+        names.sort();
+        names
+    }
+
     pub fn get_hierarchy(&self) -> Vec<(String, Vec<(String, Vec<String>)>)> {
         let mut temp_module_groups: HashMap<String, HashMap<String, Vec<String>>> = HashMap::new();
 
@@ -1,7 +1,7 @@
 use std::{
     any::Any,
     cell::RefCell,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     f32,
     rc::{Rc, Weak},
 };
@@ -13,11 +13,17 @@ use pyo3::{
 };
 use raylib::prelude::*;
 use raylib_sys::{rlPopMatrix, rlPushMatrix, rlTranslatef};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{
+    annotation::DOUBLE_CLICK_TIME,
     colorscheme::ColorSchemes,
     draw::PyDrawHandle,
-    objects::{self, Camera, Object, PyColorSchemes, PyObject},
+    history::Command,
+    objects::{self, Camera, Object, PyColorSchemes, PyObject, TextBox},
+    routing::{self, WireStyle},
+    save::NodeSave,
     settings::Settings,
     structs::Vector2,
     translations::Translations,
@@ -28,8 +34,18 @@ pub struct Port {
     data: Py<PyAny>,
     pub position: Vector2,
     border_color: Color,
+    pub color: Color,
     pub parent_id: String,
     pub label: String,
+    pub data_type: String,
+}
+
+pub fn types_compatible(output: &str, input: &str) -> bool {
+    match (output, input) {
+        ("any", _) | (_, "any") => true,
+        ("int", "float") | ("int", "number") | ("float", "number") | ("number", "float") => true,
+        _ => output == input,
+    }
 }
 
 impl Object for Port {
@@ -38,7 +54,7 @@ impl Object for Port {
             self.position.x as i32,
             self.position.y as i32,
             6.0,
-            Color::ORANGE,
+            self.color,
         );
         draw_handle.draw_circle_lines(
             self.position.x as i32,
@@ -60,6 +76,16 @@ impl Object for Port {
                     self.border_color = *v;
                 }
             }
+            "color" => {
+                if let Ok(v) = value.downcast::<Color>() {
+                    self.color = *v;
+                }
+            }
+            "data_type" => {
+                if let Ok(v) = value.downcast::<String>() {
+                    self.data_type = *v;
+                }
+            }
             "data" => {
                 if let Ok(v) = value.downcast::<Py<PyAny>>() {
                     self.data = *v;
@@ -75,6 +101,8 @@ impl Object for Port {
         match key.as_str() {
             "position" => Box::new(self.position.clone()),
             "border_color" => Box::new(self.border_color),
+            "color" => Box::new(self.color),
+            "data_type" => Box::new(self.data_type.clone()),
             _ => {
                 eprintln!("Port: bilinmeyen özellik '{}'", key);
                 Box::new(())
@@ -97,8 +125,10 @@ impl Port {
             data: py.None().into(),
             position: Vector2::zero(),
             border_color,
+            color: Color::ORANGE,
             parent_id: "".to_string(),
             label: "".to_string(),
+            data_type: "any".to_string(),
         })
     }
 
@@ -111,14 +141,101 @@ impl Port {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ConnectionKey {
+    pub from_node: String,
+    pub from_port: String,
+    pub to_node: String,
+    pub to_port: String,
+}
+
+impl ConnectionKey {
+    pub fn between(from: &Port, to: &Port) -> Self {
+        Self {
+            from_node: from.parent_id.clone(),
+            from_port: from.label.clone(),
+            to_node: to.parent_id.clone(),
+            to_port: to.label.clone(),
+        }
+    }
+
+    pub fn touches(&self, node_id: &str) -> bool {
+        self.from_node == node_id || self.to_node == node_id
+    }
+
+    /// Old projects stored connections as `((node):("port"))((node):("port"))`.
+    pub fn parse_legacy(s: &str) -> Option<Self> {
+        fn parse_side(side: &str) -> Option<(String, String)> {
+            let (node, port) = side.split_once("):(")?;
+            Some((node.to_string(), port.trim_matches('"').to_string()))
+        }
+
+        let (left, right) = s.split_once("))((")?;
+        let (from_node, from_port) = parse_side(left.trim_start_matches("(("))?;
+        let (to_node, to_port) = parse_side(right.trim_end_matches("))"))?;
+
+        Some(Self {
+            from_node,
+            from_port,
+            to_node,
+            to_port,
+        })
+    }
+}
+
 pub struct Connection {
     pub from: Rc<RefCell<Box<Port>>>,
     pub to: Rc<RefCell<Box<Port>>>,
     pub z: i32,
+    /// Points the wire passes through on its way from `from` to `to`.
+    pub reroutes: Vec<Vector2>,
+    /// Routing for this wire alone; `None` follows the `wire_style` setting.
+    pub style: Option<WireStyle>,
+    /// Polyline of each leg of `path`, refreshed by `update_route`.
+    pub route: Vec<Vec<Vector2>>,
 }
 
-impl Object for Connection {
-    fn update(&mut self, rl: &mut RaylibHandle, _: &RaylibThread, _: &Camera) {
+/// Radius of a reroute dot, in world units.
+pub const REROUTE_RADIUS: f32 = 6.0;
+
+impl Connection {
+    pub fn new(from: Rc<RefCell<Box<Port>>>, to: Rc<RefCell<Box<Port>>>) -> Self {
+        Self {
+            from,
+            to,
+            z: 1,
+            reroutes: Vec::new(),
+            style: None,
+            route: Vec::new(),
+        }
+    }
+
+    /// Output port, reroute points and input port in drawing order.
+    pub fn path(&self) -> Vec<Vector2> {
+        let mut path = vec![self.from.borrow().position.clone()];
+        path.extend(self.reroutes.iter().cloned());
+        path.push(self.to.borrow().position.clone());
+        path
+    }
+
+    /// Index of the reroute point under `point`, if any.
+    pub fn reroute_at(&self, point: &Vector2) -> Option<usize> {
+        self.reroutes
+            .iter()
+            .position(|p| (p.clone() - point.clone()).magnitude() <= REROUTE_RADIUS + 2.0)
+    }
+
+    /// Recomputes `route` for the current port and reroute positions.
+    pub fn update_route(&mut self, default_style: WireStyle, obstacles: &[Rectangle]) {
+        self.route = routing::route(
+            self.style.unwrap_or(default_style),
+            &self.path(),
+            (true, true),
+            obstacles,
+        );
+    }
+
+    pub fn propagate(&self) {
         let from = self.from.borrow();
         let mut to = self.to.borrow_mut();
 
@@ -126,12 +243,19 @@ impl Object for Connection {
             to.write(from.read(py));
         });
     }
+}
 
+impl Object for Connection {
     fn draw(&self, draw_handle: &mut RaylibDrawHandle, _camera: &Camera) {
-        let from_pos = self.from.borrow().position.clone();
-        let to_pos = self.to.borrow().position.clone();
+        let color = self.from.borrow().color;
 
-        draw_handle.draw_line_bezier(from_pos, to_pos, 3.0, Color::ORANGE);
+        for leg in &self.route {
+            let points: Vec<raylib::prelude::Vector2> = leg.iter().map(Into::into).collect();
+            draw_handle.draw_spline_linear(&points, 3.0, color);
+        }
+        for point in &self.reroutes {
+            draw_handle.draw_circle_v(point.clone(), REROUTE_RADIUS, color);
+        }
     }
 
     fn set_property(&mut self, key: String, value: Box<dyn Any>) {
@@ -176,6 +300,9 @@ impl Object for Connection {
     }
 }
 
+pub type ComponentValues = BTreeMap<String, BTreeMap<String, Value>>;
+pub type NodeState = serde_json::Map<String, Value>;
+
 pub struct Node {
     pub position: Vector2,
     pub size: Vector2,
@@ -183,11 +310,16 @@ pub struct Node {
     pub ports: Vec<(String, bool, i32, Rc<RefCell<Box<Port>>>)>,
     mouse_offset: Option<Vector2>,
     pub active: bool,
+    pub selected: bool,
     pub roundness: f32,
-    pub font: Rc<RefCell<Font>>,
+    /// `None` when evaluating without a window; such nodes are never drawn.
+    pub font: Option<Rc<RefCell<Font>>>,
     pub title_height: f32,
     pub update_fn: Option<Rc<Py<PyAny>>>,
     pub draw_fn: Option<Rc<Py<PyAny>>>,
+    /// Whether `draw_fn` takes `state`; older draw functions only take the node and handle.
+    draw_takes_state: bool,
+    pub state: Rc<Py<PyDict>>,
     pub type_name: String,
     pub id: String,
     pub translations: Rc<RefCell<Translations>>,
@@ -196,8 +328,28 @@ pub struct Node {
     pub rc_self: Option<Weak<RefCell<Node>>>,
     pub scalable: bool,
     pub z: i32,
+    pub error: Option<String>,
+    /// Save of a node whose type isn't loaded, written back unchanged.
+    pub missing: Option<NodeSave>,
+    /// Skips `update_fn` and hands inputs straight to outputs.
+    pub bypassed: bool,
+    /// Context menu entries added by the node's module, as label and callback.
+    pub menu_items: Vec<(String, Rc<Py<PyAny>>)>,
+    /// Name given to this instance, shown instead of the translated type title.
+    pub title: Option<String>,
+    /// Color tag of the header, one of `NODE_COLORS`.
+    pub color: Option<String>,
+    /// Title being typed while the node is renamed.
+    title_edit: Option<String>,
+    /// Shows only the title bar, with the ports stacked on its edges.
+    pub collapsed: bool,
+    last_title_click: f64,
+    draw_error: RefCell<Option<String>>,
 }
 
+/// Color tags a node header can get, each drawn with the `node_tag_<name>` scheme color.
+pub const NODE_COLORS: [&str; 6] = ["red", "orange", "yellow", "green", "blue", "purple"];
+
 impl Object for Node {
     fn update(&mut self, rl_handle: &mut RaylibHandle, rl_thread: &RaylibThread, camera: &Camera) {
         let mouse_pos =
@@ -206,25 +358,72 @@ impl Object for Node {
             x: self.position.x,
             y: self.position.y - self.title_height - 2.0,
             width: self.size.x,
-            height: self.size.y + 2.0,
+            height: if self.collapsed {
+                self.title_height
+            } else {
+                self.size.y
+            } + 2.0,
         };
 
         let origin = self.position.from_origin() + Vector2::new(5.0, 5.0, None);
-        for (_, (offset, component)) in &mut self.components {
+        let before = self.component_values();
+        /* Components of a collapsed node are hidden and left alone */
+        for (_, (offset, component)) in self.components.iter_mut().filter(|_| !self.collapsed) {
             component.borrow_mut().set_property(
                 "position".to_string(),
                 Box::new(origin.clone() + offset.clone()),
             );
             component.borrow_mut().update(rl_handle, rl_thread, camera);
         }
+        let after = self.component_values();
+        if after != before {
+            EDITOR_STATE.with(|state| {
+                let mut state = state.borrow_mut();
+                state.evaluation_requested = true;
+                state.history.push(Command::EditComponents {
+                    node_id: self.id.clone(),
+                    before,
+                    after,
+                });
+            });
+        }
+
+        if self.title_edit.is_some() {
+            self.update_title_edit(rl_handle, &mouse_pos);
+        }
 
         /* Drag and drop */
-        if (rl_handle.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
-            || rl_handle.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT))
+        let left_pressed = rl_handle.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+        if (left_pressed || rl_handle.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT))
+            && self.title_edit.is_none()
             && Rectangle::from(rect).check_collision_point_rec(mouse_pos.clone())
         {
+            /* A right press only targets the node for its context menu, it never moves it */
             self.active = true;
-            self.mouse_offset = Some(mouse_pos.clone() - self.position.clone());
+            self.mouse_offset = left_pressed.then(|| mouse_pos.clone() - self.position.clone());
+        }
+
+        /* Double-clicking the title collapses or expands the node */
+        if rl_handle.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            && self.title_edit.is_none()
+        {
+            let now = rl_handle.get_time();
+            if self.header().check_collision_point_rec(mouse_pos.clone()) {
+                if now - self.last_title_click <= DOUBLE_CLICK_TIME {
+                    self.collapsed = !self.collapsed;
+                    EDITOR_STATE.with(|state| {
+                        state.borrow_mut().history.push(Command::SetCollapsed(vec![(
+                            self.id.clone(),
+                            self.collapsed,
+                        )]));
+                    });
+                    self.last_title_click = 0.0;
+                } else {
+                    self.last_title_click = now;
+                }
+            } else {
+                self.last_title_click = 0.0;
+            }
         }
 
         if rl_handle.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
@@ -240,19 +439,31 @@ impl Object for Node {
             self.mouse_offset = None;
         }
 
-        for (_, (label, is_output, y_offset, port)) in self.ports.iter().enumerate() {
+        let outputs = self
+            .ports
+            .iter()
+            .filter(|(_, is_output, _, _)| *is_output)
+            .count();
+        let side_counts = [self.ports.len() - outputs, outputs];
+        let mut side_indices = [0, 0];
+        for (_, is_output, y_offset, port) in self.ports.iter() {
+            let side = *is_output as usize;
+            let y = if self.collapsed {
+                self.position.y - self.title_height
+                    + self.title_height * (side_indices[side] + 1) as f32
+                        / (side_counts[side] + 1) as f32
+            } else {
+                self.position.y + *y_offset as f32
+            };
+            side_indices[side] += 1;
             let port_position = Vector2::new(
                 self.position.x + if *is_output { self.size.x } else { 0.0 },
-                self.position.y + *y_offset as f32,
+                y,
                 None,
             );
 
-            {
-                let mut port_borrow = port.borrow_mut();
-                port_borrow.set_property("position".to_string(), Box::new(port_position.clone()));
-                port_borrow.parent_id = self.id.to_string();
-                port_borrow.label = label.clone();
-            }
+            port.borrow_mut()
+                .set_property("position".to_string(), Box::new(port_position.clone()));
 
             if (port_position.clone() - mouse_pos.clone()).magnitude() <= 6.0 {
                 self.active = false;
@@ -270,41 +481,14 @@ impl Object for Node {
             }
         }
 
-        if let Some(update_fn) = self.update_fn.take() {
-            let _ = Python::attach(|py| -> PyResult<()> {
-                let inputs = self.get_inputs_py_dict(py);
-
-                let kwargs = PyDict::new(py);
-                kwargs.set_item("inputs", &inputs)?;
-                kwargs.set_item("components", self.get_components_py_dict(py))?;
-
-                let outputs: HashMap<String, Py<PyAny>> = update_fn
-                    .call(py, PyTuple::empty(py), Some(&kwargs))
-                    .expect(
-                        format!("Update function of {} throwed an error", self.type_name).as_str(),
-                    )
-                    .extract(py)
-                    .unwrap_or(HashMap::new());
-
-                for (label, value) in &outputs {
-                    self.write_port(label, value.clone_ref(py));
-                }
-
-                Ok(())
-            });
-            self.update_fn = Some(update_fn);
-        }
-
         self.fit_around_components();
     }
 
     fn draw(&self, draw_handle: &mut RaylibDrawHandle, camera: &Camera) {
-        let rect = Rectangle::new(
-            self.position.x,
-            self.position.y - self.title_height,
-            self.size.x,
-            self.size.y + self.title_height,
-        );
+        let Some(font) = &self.font else {
+            return;
+        };
+        let rect = self.bounds();
 
         let schemes = self.color_schemes.borrow();
         let scheme = self.settings.borrow().scheme.to_string();
@@ -312,7 +496,16 @@ impl Object for Node {
             .get_color(&scheme, "node_background")
             .unwrap_or(Color::MAGENTA);
         let border_color = schemes
-            .get_color(&scheme, "node_border")
+            .get_color(
+                &scheme,
+                if self.missing.is_some() {
+                    "node_missing_border"
+                } else if self.bypassed {
+                    "node_bypassed_border"
+                } else {
+                    "node_border"
+                },
+            )
             .unwrap_or(Color::MAGENTA);
         let active_border_color = schemes
             .get_color(&scheme, "node_active_border")
@@ -321,7 +514,33 @@ impl Object for Node {
             .get_color(&scheme, "node_foreground")
             .unwrap_or(Color::MAGENTA);
 
-        draw_handle.draw_rectangle_rounded(rect, self.roundness, 32, background_color);
+        match self
+            .color
+            .as_ref()
+            .and_then(|color| schemes.get_color(&scheme, &format!("node_tag_{}", color)))
+        {
+            Some(tag_color) if self.collapsed => {
+                draw_handle.draw_rectangle_rounded(rect, self.roundness, 32, tag_color)
+            }
+            Some(tag_color) => {
+                /* Body drawn over a tag colored node, square at the top so only the header shows */
+                let radius = self.roundness * rect.width.min(rect.height) / 2.0;
+                let body =
+                    Rectangle::new(self.position.x, self.position.y, self.size.x, self.size.y);
+                draw_handle.draw_rectangle_rounded(rect, self.roundness, 32, tag_color);
+                draw_handle.draw_rectangle_rounded(
+                    body,
+                    radius * 2.0 / body.width.min(body.height).max(1.0),
+                    32,
+                    background_color,
+                );
+                draw_handle.draw_rectangle_rec(
+                    Rectangle::new(body.x, body.y, body.width, radius.min(body.height)),
+                    background_color,
+                );
+            }
+            None => draw_handle.draw_rectangle_rounded(rect, self.roundness, 32, background_color),
+        }
         //draw_handle.draw_rectangle_pro(rect, Vector2::zero(), 0.0, self.background_color);
 
         draw_handle.draw_rectangle_rounded_lines_ex(
@@ -329,7 +548,7 @@ impl Object for Node {
             self.roundness,
             32,
             3.0,
-            if self.active {
+            if self.active || self.selected {
                 active_border_color
             } else {
                 border_color
@@ -338,34 +557,32 @@ impl Object for Node {
         //draw_handle.draw_rectangle_lines_ex(rect, 3.0, border_color);
 
         /* Draw Title */
-        draw_handle.draw_line_ex(
-            self.position.clone(),
-            self.position.clone() + Vector2::new(self.size.x, 0.0, None),
-            3.0,
-            if self.active {
-                active_border_color
-            } else {
-                border_color
-            },
-        );
+        if !self.collapsed {
+            draw_handle.draw_line_ex(
+                self.position.clone(),
+                self.position.clone() + Vector2::new(self.size.x, 0.0, None),
+                3.0,
+                if self.active || self.selected {
+                    active_border_color
+                } else {
+                    border_color
+                },
+            );
+        }
 
-        let title = self
-            .translations
-            .borrow()
-            .get_node_translation(
-                self.settings.borrow().language.as_str(),
-                self.type_name.as_str(),
-            )
-            .title;
+        let title = match &self.title_edit {
+            Some(text) => format!("{}_", text),
+            None => self.display_title(),
+        };
         let text_size = self.title_height - 2.0;
-        let text_spacing = self.font.borrow().measure_text(&title, text_size, 1.0);
+        let text_spacing = font.borrow().measure_text(&title, text_size, 1.0);
         let text_pos = Vector2::new(
             self.position.x + (self.size.x - text_spacing.x) / 2.0,
             self.position.y - self.title_height + (self.title_height - text_spacing.y) / 2.0,
             None,
         );
         draw_handle.draw_text_ex(
-            &*self.font.borrow(),
+            &*font.borrow(),
             &title,
             text_pos,
             text_size,
@@ -374,7 +591,9 @@ impl Object for Node {
         );
 
         /* Custom Draw Function */
-        if let Some(draw_fn) = &self.draw_fn {
+        if let Some(draw_fn) = &self.draw_fn
+            && !self.collapsed
+        {
             unsafe {
                 rlPushMatrix();
                 rlTranslatef(self.position.x, self.position.y + 2.0, 0.0);
@@ -398,11 +617,25 @@ impl Object for Node {
 
                         let pydraw = PyDrawHandle {
                             draw_handle: ptr,
-                            font: self.font.clone(),
+                            font: font.clone(),
                         };
 
-                        if let Err(e) = draw_fn.call(py, (PyNode::from_node(self), pydraw), None) {
-                            e.print(py);
+                        let kwargs = PyDict::new(py);
+                        if self.draw_takes_state {
+                            kwargs.set_item("state", self.state.bind(py)).unwrap();
+                        }
+
+                        match draw_fn.call(py, (PyNode::from_node(self), pydraw), Some(&kwargs)) {
+                            Ok(_) => {
+                                self.draw_error.replace(None);
+                            }
+                            Err(e) => {
+                                let message = format_py_error(py, &e);
+                                if self.draw_error.borrow().as_ref() != Some(&message) {
+                                    eprintln!("Draw function of {} failed:\n{}", self.id, message);
+                                }
+                                self.draw_error.replace(Some(message));
+                            }
                         }
                     },
                 );
@@ -416,50 +649,58 @@ impl Object for Node {
         /* Draw Components */
         let screen_pos = draw_handle
             .get_world_to_screen2D(self.position.clone(), Camera2D::from(camera.clone()));
-        draw_handle.draw_scissor_mode(
-            screen_pos.x as i32 + 5,
-            screen_pos.y as i32 + 5,
-            ((self.size.x - 10.0) * camera.zoom) as i32,
-            ((self.size.y - 10.0) * camera.zoom) as i32,
-            |mut scissor| {
-                for (_, (_, component)) in &self.components {
-                    let mut comp = component.borrow_mut();
-                    comp.set_property(
-                        "background_color".to_string(),
-                        Box::new(
-                            schemes
-                                .get_color(&scheme, "node_component_background")
-                                .unwrap(),
-                        ),
-                    );
-                    comp.set_property(
-                        "foreground_color".to_string(),
-                        Box::new(
-                            schemes
-                                .get_color(&scheme, "node_component_foreground")
-                                .unwrap(),
-                        ),
-                    );
-                    comp.set_property(
-                        "active_background_color".to_string(),
-                        Box::new(
-                            schemes
-                                .get_color(&scheme, "node_component_active_background")
-                                .unwrap(),
-                        ),
-                    );
-                    comp.draw(&mut scissor, camera);
-                }
-            },
-        );
+        if !self.collapsed {
+            draw_handle.draw_scissor_mode(
+                screen_pos.x as i32 + 5,
+                screen_pos.y as i32 + 5,
+                ((self.size.x - 10.0) * camera.zoom) as i32,
+                ((self.size.y - 10.0) * camera.zoom) as i32,
+                |mut scissor| {
+                    for (_, (_, component)) in &self.components {
+                        let mut comp = component.borrow_mut();
+                        comp.set_property(
+                            "background_color".to_string(),
+                            Box::new(
+                                schemes
+                                    .get_color(&scheme, "node_component_background")
+                                    .unwrap(),
+                            ),
+                        );
+                        comp.set_property(
+                            "foreground_color".to_string(),
+                            Box::new(
+                                schemes
+                                    .get_color(&scheme, "node_component_foreground")
+                                    .unwrap(),
+                            ),
+                        );
+                        comp.set_property(
+                            "active_background_color".to_string(),
+                            Box::new(
+                                schemes
+                                    .get_color(&scheme, "node_component_active_background")
+                                    .unwrap(),
+                            ),
+                        );
+                        comp.draw(&mut scissor, camera);
+                    }
+                },
+            );
+        }
 
         /* Draw inputs and outputs */
         for (label, is_output, _, port) in self.ports.iter() {
+            let port_color = schemes.get_type_color(&scheme, &port.borrow().data_type);
+            port.borrow_mut().color = port_color;
+
             let port_pos = port.borrow().position.clone();
             port.borrow().draw(draw_handle, camera);
+            if self.collapsed {
+                continue;
+            }
 
             let text_size = 16.0;
-            let text_spacing = self.font.borrow().measure_text(label, text_size, 1.0);
+            let text_spacing = font.borrow().measure_text(label, text_size, 1.0);
             let text_pos = Vector2::new(
                 port_pos.x
                     + if *is_output {
@@ -471,7 +712,7 @@ impl Object for Node {
                 None,
             );
             draw_handle.draw_text_ex(
-                &*self.font.borrow(),
+                &*font.borrow(),
                 label,
                 text_pos,
                 text_size,
@@ -479,6 +720,29 @@ impl Object for Node {
                 foreground_color,
             );
         }
+
+        /* Error badge */
+        if self.error.is_some() || self.draw_error.borrow().is_some() {
+            let center = self.error_badge_center();
+            let text_spacing = font.borrow().measure_text("!", 16.0, 1.0);
+            draw_handle.draw_circle_v(
+                center.clone(),
+                9.0,
+                schemes
+                    .get_color(&scheme, "node_error")
+                    .unwrap_or(Color::RED),
+            );
+            draw_handle.draw_text_ex(
+                &*font.borrow(),
+                "!",
+                center - Vector2::from(text_spacing) / 2.0,
+                16.0,
+                1.0,
+                schemes
+                    .get_color(&scheme, "node_error_foreground")
+                    .unwrap_or(Color::WHITE),
+            );
+        }
     }
 
     fn set_property(&mut self, key: String, value: Box<dyn Any>) {
@@ -498,6 +762,11 @@ impl Object for Node {
                     self.active = *v;
                 }
             }
+            "selected" => {
+                if let Ok(v) = value.downcast::<bool>() {
+                    self.selected = *v;
+                }
+            }
             "roundness" => {
                 if let Ok(v) = value.downcast::<f32>() {
                     self.roundness = *v;
@@ -517,6 +786,7 @@ impl Object for Node {
             "position" => Box::new(self.position.clone()),
             "size" => Box::new(self.size.clone()),
             "active" => Box::new(self.active),
+            "selected" => Box::new(self.selected),
             "roundness" => Box::new(self.roundness),
             "id" => Box::new(self.id.clone()),
             "type_name" => Box::new(self.type_name.to_string()),
@@ -542,7 +812,7 @@ impl Node {
     pub fn new(
         position: Vector2,
         size: Vector2,
-        font: Rc<RefCell<Font>>,
+        font: Option<Rc<RefCell<Font>>>,
         update_fn: Option<Py<PyAny>>,
         draw_fn: Option<Py<PyAny>>,
         type_name: String,
@@ -557,12 +827,17 @@ impl Node {
             size,
             components: HashMap::new(),
             active: false,
+            selected: false,
             roundness: 0.2,
             font,
             title_height: 24.0,
             mouse_offset: None,
             update_fn: update_fn.map(|x| Rc::new(x)),
+            draw_takes_state: draw_fn
+                .as_ref()
+                .is_some_and(|draw_fn| Python::attach(|py| accepts_keyword(py, draw_fn, "state"))),
             draw_fn: draw_fn.map(|x| Rc::new(x)),
+            state: Rc::new(Python::attach(|py| PyDict::new(py).unbind())),
             type_name,
             id,
             ports: vec![],
@@ -572,6 +847,16 @@ impl Node {
             scalable,
             rc_self: None,
             z: 0,
+            error: None,
+            missing: None,
+            bypassed: false,
+            menu_items: vec![],
+            title: None,
+            color: None,
+            title_edit: None,
+            collapsed: false,
+            last_title_click: 0.0,
+            draw_error: RefCell::new(None),
         }));
 
         {
@@ -581,13 +866,84 @@ impl Node {
         node
     }
 
+    pub fn placeholder(
+        save: &NodeSave,
+        connections: &[ConnectionKey],
+        font: Option<Rc<RefCell<Font>>>,
+        translations: Rc<RefCell<Translations>>,
+        color_schemes: Rc<RefCell<ColorSchemes>>,
+        settings: Rc<RefCell<Settings>>,
+    ) -> Rc<RefCell<Self>> {
+        let mut inputs: Vec<&str> = vec![];
+        let mut outputs: Vec<&str> = vec![];
+        for key in connections {
+            if key.to_node == save.id && !inputs.contains(&key.to_port.as_str()) {
+                inputs.push(&key.to_port);
+            }
+            if key.from_node == save.id && !outputs.contains(&key.from_port.as_str()) {
+                outputs.push(&key.from_port);
+            }
+        }
+
+        let rows = inputs.len().max(outputs.len()).max(1);
+        let border_color = color_schemes
+            .borrow()
+            .get_color(&settings.borrow().scheme, "node_border")
+            .unwrap_or(Color::MAGENTA);
+        let message = format!(
+            "{} {}",
+            translations
+                .borrow()
+                .get_gui_translation(&settings.borrow().language, "node.missing"),
+            save.type_name
+        );
+
+        let node = Node::new(
+            save.position.into(),
+            Vector2::new(160.0, rows as f32 * 24.0 + 16.0, None),
+            font,
+            None,
+            None,
+            save.type_name.clone(),
+            translations,
+            color_schemes,
+            settings,
+            save.id.clone(),
+            false,
+        );
+
+        for (labels, is_output) in [(inputs, false), (outputs, true)] {
+            for (i, label) in labels.into_iter().enumerate() {
+                let y_offset = 20 + i as i32 * 24;
+                Node::add_port(
+                    &node,
+                    Box::new(Port::new(border_color)),
+                    label,
+                    is_output,
+                    y_offset,
+                );
+            }
+        }
+
+        {
+            let mut node = node.borrow_mut();
+            node.error = Some(message);
+            node.missing = Some(save.clone());
+        }
+
+        node
+    }
+
     pub fn add_port(
         this: &Rc<RefCell<Self>>,
-        port: Box<Port>,
+        mut port: Box<Port>,
         label: &str,
         is_output: bool,
         y_offset: i32,
     ) {
+        port.parent_id = this.borrow().id.clone();
+        port.label = label.to_string();
+
         this.borrow_mut().ports.push((
             label.to_string(),
             is_output,
@@ -617,6 +973,218 @@ impl Node {
         );
     }
 
+    pub fn evaluate(&mut self) {
+        if self.bypassed {
+            self.pass_through();
+            self.error = None;
+            return;
+        }
+
+        if let Some(update_fn) = self.update_fn.take() {
+            let result = Python::attach(|py| -> Result<(), String> {
+                let inputs = self.get_inputs_py_dict(py);
+
+                let call = || -> PyResult<Py<PyAny>> {
+                    let kwargs = PyDict::new(py);
+                    kwargs.set_item("inputs", &inputs)?;
+                    kwargs.set_item("components", self.get_components_py_dict(py))?;
+                    kwargs.set_item("state", self.state.bind(py))?;
+                    update_fn.call(py, PyTuple::empty(py), Some(&kwargs))
+                };
+
+                let outputs: HashMap<String, Py<PyAny>> = call()
+                    .map_err(|e| format_py_error(py, &e))?
+                    .extract(py)
+                    .unwrap_or(HashMap::new());
+
+                for (label, value) in &outputs {
+                    self.write_port(label, value.clone_ref(py));
+                }
+
+                Ok(())
+            });
+            self.update_fn = Some(update_fn);
+
+            match result {
+                Ok(()) => self.error = None,
+                Err(message) => {
+                    eprintln!("Update function of {} failed:\n{}", self.id, message);
+                    self.clear_outputs();
+                    self.error = Some(message);
+                }
+            }
+        }
+    }
+
+    /// Writes each input to the output at the same position, or the first input when there
+    /// are more outputs than inputs.
+    fn pass_through(&mut self) {
+        Python::attach(|py| {
+            let inputs: Vec<Py<PyAny>> = self
+                .ports
+                .iter()
+                .filter(|(_, is_output, _, _)| !*is_output)
+                .map(|(_, _, _, port)| port.borrow().read(py))
+                .collect();
+            let outputs = self.ports.iter().filter(|(_, is_output, _, _)| *is_output);
+
+            for (i, (_, _, _, port)) in outputs.enumerate() {
+                let value = inputs
+                    .get(i)
+                    .or(inputs.first())
+                    .map(|value| value.clone_ref(py))
+                    .unwrap_or_else(|| py.None());
+                port.borrow_mut().write(value);
+            }
+        });
+    }
+
+    /// Calls the callback of module menu entry `index` with the arguments `update_fn` gets.
+    pub fn run_menu_item(&mut self, index: usize) {
+        let Some((label, callback)) = self.menu_items.get(index).cloned() else {
+            return;
+        };
+
+        let result = Python::attach(|py| {
+            let call = || -> PyResult<Py<PyAny>> {
+                let kwargs = PyDict::new(py);
+                kwargs.set_item("inputs", self.get_inputs_py_dict(py))?;
+                kwargs.set_item("components", self.get_components_py_dict(py))?;
+                kwargs.set_item("state", self.state.bind(py))?;
+                callback.call(py, PyTuple::empty(py), Some(&kwargs))
+            };
+
+            call().map(|_| ()).map_err(|e| format_py_error(py, &e))
+        });
+
+        if let Err(message) = result {
+            eprintln!("Menu entry '{}' of {} failed:\n{}", label, self.id, message);
+            self.error = Some(message);
+        }
+    }
+
+    pub fn clear_outputs(&mut self) {
+        Python::attach(|py| {
+            for (_, is_output, _, port) in &self.ports {
+                if *is_output {
+                    port.borrow_mut().write(py.None());
+                }
+            }
+        });
+    }
+
+    fn error_badge_center(&self) -> Vector2 {
+        Vector2::new(
+            self.position.x + self.size.x - self.title_height / 2.0,
+            self.position.y - self.title_height / 2.0,
+            None,
+        )
+    }
+
+    pub fn draw_error_tooltip(&self, draw_handle: &mut RaylibDrawHandle, camera: &Camera) {
+        let Some(message) = self
+            .error
+            .clone()
+            .or_else(|| self.draw_error.borrow().clone())
+        else {
+            return;
+        };
+
+        let mouse = Vector2::from(
+            draw_handle.get_screen_to_world2D(draw_handle.get_mouse_position(), camera),
+        );
+        let center = self.error_badge_center();
+        if (mouse - center.clone()).magnitude() > 9.0 {
+            return;
+        }
+
+        let schemes = self.color_schemes.borrow();
+        let scheme = self.settings.borrow().scheme.to_string();
+        let Some(font) = &self.font else {
+            return;
+        };
+        let font = font.borrow();
+        let text_size = 14.0;
+        let lines: Vec<&str> = message.lines().collect();
+        let width = lines
+            .iter()
+            .map(|line| font.measure_text(line, text_size, 1.0).x)
+            .fold(0.0, f32::max);
+        let rect = Rectangle::new(
+            center.x + 14.0,
+            center.y,
+            width + 16.0,
+            lines.len() as f32 * (text_size + 2.0) + 12.0,
+        );
+
+        draw_handle.draw_rectangle_rec(
+            rect,
+            schemes
+                .get_color(&scheme, "dialog_background")
+                .unwrap_or(Color::BLACK),
+        );
+        draw_handle.draw_rectangle_lines_ex(
+            rect,
+            2.0,
+            schemes
+                .get_color(&scheme, "node_error")
+                .unwrap_or(Color::RED),
+        );
+
+        for (i, line) in lines.iter().enumerate() {
+            draw_handle.draw_text_ex(
+                &*font,
+                line,
+                Vector2::new(
+                    rect.x + 8.0,
+                    rect.y + 6.0 + i as f32 * (text_size + 2.0),
+                    None,
+                ),
+                text_size,
+                1.0,
+                schemes
+                    .get_color(&scheme, "dialog_foreground")
+                    .unwrap_or(Color::WHITE),
+            );
+        }
+    }
+
+    pub fn component_values(&self) -> ComponentValues {
+        let mut values = ComponentValues::new();
+
+        for (name, (_, component)) in &self.components {
+            let component = component.borrow();
+            let properties: BTreeMap<String, Value> = component
+                .persistent_properties()
+                .into_iter()
+                .filter_map(|key| {
+                    property_to_json(component.get_property(key.to_string()))
+                        .map(|value| (key.to_string(), value))
+                })
+                .collect();
+
+            if !properties.is_empty() {
+                values.insert(name.clone(), properties);
+            }
+        }
+
+        values
+    }
+
+    pub fn apply_component_values(&mut self, values: &ComponentValues) {
+        for (name, properties) in values {
+            if let Some((_, component)) = self.components.get(name) {
+                let mut component = component.borrow_mut();
+                for (key, value) in properties {
+                    let current = component.get_property(key.clone());
+                    if let Some(converted) = json_to_property(value, &*current) {
+                        component.set_property(key.clone(), converted);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn get_inputs(&self) -> Vec<(String, &Rc<RefCell<Box<Port>>>)> {
         self.ports
             .iter()
@@ -648,6 +1216,44 @@ impl Node {
         dict.into()
     }
 
+    /// Entries json can't encode are reported and left out.
+    pub fn state_values(&self) -> NodeState {
+        Python::attach(|py| {
+            let mut values = NodeState::new();
+            for (key, value) in self.state.bind(py).iter() {
+                let key = key.to_string();
+                match py_to_json(py, &value) {
+                    Ok(value) => {
+                        values.insert(key, value);
+                    }
+                    Err(e) => eprintln!(
+                        "State entry '{}' of {} could not be saved:\n{}",
+                        key,
+                        self.id,
+                        format_py_error(py, &e)
+                    ),
+                }
+            }
+            values
+        })
+    }
+
+    pub fn restore_state(&mut self, values: &NodeState) {
+        Python::attach(|py| {
+            let state = self.state.bind(py);
+            state.clear();
+
+            for (key, value) in values {
+                match json_to_py(py, value) {
+                    Ok(value) => {
+                        let _ = state.set_item(key, value);
+                    }
+                    Err(e) => eprintln!("{}", format_py_error(py, &e)),
+                }
+            }
+        });
+    }
+
     pub fn write_port(&mut self, label: &str, value: Py<PyAny>) {
         self.ports
             .iter()
@@ -662,6 +1268,131 @@ impl Node {
             .map(|(_, _, _, port)| port.borrow().read(py))
     }
 
+    /// Whether one of the node's text boxes has keyboard focus.
+    pub fn editing_text(&self) -> bool {
+        self.renaming()
+            || self.components.values().any(|(_, component)| {
+                component
+                    .borrow()
+                    .as_any()
+                    .downcast_ref::<TextBox>()
+                    .is_some_and(|text_box| {
+                        text_box
+                            .get_property("active".to_string())
+                            .downcast::<bool>()
+                            .is_ok_and(|active| *active)
+                    })
+            })
+    }
+
+    /// Custom title if the node has one, otherwise the translated title of its type.
+    pub fn display_title(&self) -> String {
+        if let Some(title) = &self.title {
+            return title.clone();
+        }
+        match &self.missing {
+            Some(save) => save.type_name.clone(),
+            None => {
+                self.translations
+                    .borrow()
+                    .get_node_translation(
+                        self.settings.borrow().language.as_str(),
+                        self.type_name.as_str(),
+                    )
+                    .title
+            }
+        }
+    }
+
+    pub fn renaming(&self) -> bool {
+        self.title_edit.is_some()
+    }
+
+    /// Starts typing a new title in the header, beginning with the one shown now.
+    pub fn begin_rename(&mut self) {
+        self.title_edit = Some(self.display_title());
+    }
+
+    fn update_title_edit(&mut self, rl: &mut RaylibHandle, mouse: &Vector2) {
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            && !self.header().check_collision_point_rec(mouse.clone())
+        {
+            self.finish_rename();
+            return;
+        }
+
+        while let Some(key) = rl.get_key_pressed() {
+            match key {
+                KeyboardKey::KEY_BACKSPACE => {
+                    if let Some(text) = &mut self.title_edit {
+                        text.pop();
+                    }
+                }
+                KeyboardKey::KEY_ENTER | KeyboardKey::KEY_KP_ENTER => {
+                    self.finish_rename();
+                    return;
+                }
+                KeyboardKey::KEY_ESCAPE => {
+                    self.title_edit = None;
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        while let Some(ch) = rl.get_char_pressed() {
+            if !ch.is_control()
+                && let Some(text) = &mut self.title_edit
+            {
+                text.push(ch);
+            }
+        }
+    }
+
+    /// Keeps the typed title; an empty one or the type's own title clears the custom title.
+    fn finish_rename(&mut self) {
+        let Some(text) = self.title_edit.take() else {
+            return;
+        };
+
+        let before = self.title.take();
+        let text = text.trim().to_string();
+        let after = (!text.is_empty() && text != self.display_title()).then_some(text);
+        self.title = after.clone();
+
+        if before != after {
+            EDITOR_STATE.with(|state| {
+                state.borrow_mut().history.push(Command::RenameNode {
+                    id: self.id.clone(),
+                    before,
+                    after,
+                });
+            });
+        }
+    }
+
+    /// World-space rectangle covering the title bar and, unless collapsed, the body.
+    pub fn bounds(&self) -> Rectangle {
+        if self.collapsed {
+            return self.header();
+        }
+        Rectangle::new(
+            self.position.x,
+            self.position.y - self.title_height,
+            self.size.x,
+            self.size.y + self.title_height,
+        )
+    }
+
+    fn header(&self) -> Rectangle {
+        Rectangle::new(
+            self.position.x,
+            self.position.y - self.title_height,
+            self.size.x,
+            self.title_height,
+        )
+    }
+
     pub fn fit_around_components(&mut self) {
         if !self.scalable {
             return;
@@ -679,11 +1410,99 @@ impl Node {
             max_y = max_y.max(offset.y + comp_size.y + 5.0);
         }
 
+        let settings = self.settings.borrow();
+        if settings.snap_sizes {
+            let [width, height] = settings.grid_square_size;
+            max_x = (max_x / width).ceil() * width;
+            max_y = (max_y / height).ceil() * height;
+        }
+
         self.size.x = max_x;
         self.size.y = max_y;
     }
 }
 
+fn property_to_json(value: Box<dyn Any>) -> Option<Value> {
+    if let Some(v) = value.downcast_ref::<String>() {
+        Some(Value::from(v.clone()))
+    } else if let Some(v) = value.downcast_ref::<f32>() {
+        Some(Value::from(*v as f64))
+    } else if let Some(v) = value.downcast_ref::<i32>() {
+        Some(Value::from(*v))
+    } else if let Some(v) = value.downcast_ref::<bool>() {
+        Some(Value::from(*v))
+    } else {
+        None
+    }
+}
+
+fn json_to_property(value: &Value, current: &dyn Any) -> Option<Box<dyn Any>> {
+    if current.is::<String>() {
+        value
+            .as_str()
+            .map(|v| Box::new(v.to_string()) as Box<dyn Any>)
+    } else if current.is::<f32>() {
+        value.as_f64().map(|v| Box::new(v as f32) as Box<dyn Any>)
+    } else if current.is::<i32>() {
+        value.as_i64().map(|v| Box::new(v as i32) as Box<dyn Any>)
+    } else if current.is::<bool>() {
+        value.as_bool().map(|v| Box::new(v) as Box<dyn Any>)
+    } else {
+        None
+    }
+}
+
+pub fn json_to_py(py: Python, value: &Value) -> PyResult<Py<PyAny>> {
+    Ok(py
+        .import("json")?
+        .call_method1("loads", (value.to_string(),))?
+        .unbind())
+}
+
+/// Fails with Python's `TypeError` or `ValueError` for values json can't encode, NaN included.
+pub fn py_to_json(py: Python, value: &Bound<PyAny>) -> PyResult<Value> {
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("allow_nan", false)?;
+
+    let text: String = py
+        .import("json")?
+        .call_method("dumps", (value,), Some(&kwargs))?
+        .extract()?;
+
+    serde_json::from_str(&text).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+}
+
+/// Whether `func` takes the keyword argument `name`, by name or through `**kwargs`.
+fn accepts_keyword(py: Python, func: &Py<PyAny>, name: &str) -> bool {
+    let check = || -> PyResult<bool> {
+        let parameters = py
+            .import("inspect")?
+            .call_method1("signature", (func,))?
+            .getattr("parameters")?;
+        if parameters.contains(name)? {
+            return Ok(true);
+        }
+        for parameter in parameters.call_method0("values")?.try_iter()? {
+            let kind: String = parameter?.getattr("kind")?.getattr("name")?.extract()?;
+            if kind == "VAR_KEYWORD" {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    };
+
+    check().unwrap_or(false)
+}
+
+pub fn format_py_error(py: Python, error: &PyErr) -> String {
+    let traceback = error
+        .traceback(py)
+        .and_then(|traceback| traceback.format().ok())
+        .unwrap_or_default();
+
+    format!("{}{}", traceback, error)
+}
+
 impl Into<Rectangle> for Node {
     fn into(self) -> Rectangle {
         Rectangle {
@@ -727,13 +1546,15 @@ impl Into<objects::RoundedRectangle> for Node {
 pub struct PyNode {
     pub size: Vector2,
     pub components: HashMap<String, (Vector2, PyObject)>,
-    pub ports: Vec<(String, bool, i32)>,
+    pub ports: Vec<(String, bool, i32, String)>,
     pub update_fn: Option<Rc<Py<PyAny>>>,
     pub draw_fn: Option<Rc<Py<PyAny>>>,
+    pub state: Option<Rc<Py<PyDict>>>,
     pub type_name: String,
     pub scalable: bool,
     pub colorscheme: Option<PyColorSchemes>,
     pub scheme: Option<String>,
+    pub menu_items: Vec<(String, Rc<Py<PyAny>>)>,
 }
 
 #[pymethods]
@@ -750,35 +1571,64 @@ impl PyNode {
             size: size.into(),
             draw_fn: draw_fn.map(|x| Rc::new(x)),
             update_fn: update_fn.map(|x| Rc::new(x)),
+            state: None,
             type_name: type_name.clone(),
             scalable,
             components: HashMap::new(),
             ports: vec![],
             colorscheme: None,
             scheme: None,
+            menu_items: vec![],
         }
     }
 
-    pub fn add_port(&mut self, label: String, is_output: bool, y_offset: i32) {
-        self.ports.push((label.to_string(), is_output, y_offset));
+    #[pyo3(signature = (label, is_output, y_offset, data_type = None))]
+    pub fn add_port(
+        &mut self,
+        label: String,
+        is_output: bool,
+        y_offset: i32,
+        data_type: Option<String>,
+    ) {
+        self.ports.push((
+            label.to_string(),
+            is_output,
+            y_offset,
+            data_type.unwrap_or("any".to_string()),
+        ));
     }
 
     pub fn add_component(&mut self, name: String, position: [f32; 2], component: PyObject) {
         self.components.insert(name, (position.into(), component));
     }
+
+    /// Adds a context menu entry; `callback` gets `inputs`, `components` and `state` like `update`.
+    pub fn add_menu_item(&mut self, label: String, callback: Py<PyAny>) {
+        self.menu_items.push((label, Rc::new(callback)));
+    }
+
+    #[getter]
+    pub fn state(&self, py: Python) -> Option<Py<PyDict>> {
+        self.state.as_ref().map(|state| state.clone_ref(py))
+    }
 }
 
 impl PyNode {
     pub fn to_node(
         &self,
         position: Vector2,
-        font: Rc<RefCell<Font>>,
+        font: Option<Rc<RefCell<Font>>>,
         translations: Rc<RefCell<Translations>>,
         color_schemes: Rc<RefCell<ColorSchemes>>,
         settings: Rc<RefCell<Settings>>,
         id: String,
     ) -> Rc<RefCell<Node>> {
-        Python::attach(|py| {
+        let border_color = color_schemes
+            .borrow()
+            .get_color(&settings.borrow().scheme, "node_border")
+            .unwrap_or(Color::MAGENTA);
+
+        let node = Python::attach(|py| {
             Node::new(
                 position,
                 self.size.clone(),
@@ -792,7 +1642,23 @@ impl PyNode {
                 id,
                 self.scalable,
             )
-        })
+        });
+
+        for (label, is_output, y_offset, data_type) in &self.ports {
+            let mut port = Port::new(border_color);
+            port.data_type = data_type.clone();
+
+            Node::add_port(&node, Box::new(port), label, *is_output, *y_offset);
+        }
+
+        for (name, (offset, component)) in &self.components {
+            node.borrow_mut()
+                .components
+                .insert(name.clone(), (offset.clone(), component.inner()));
+        }
+        node.borrow_mut().menu_items = self.menu_items.clone();
+
+        node
     }
 
     pub fn from_node(node: &Node) -> Self {
@@ -802,10 +1668,18 @@ impl PyNode {
                 .ports
                 .clone()
                 .iter()
-                .map(|(x, y, z, _)| (x.clone(), y.clone(), z.clone()))
+                .map(|(x, y, z, port)| {
+                    (
+                        x.clone(),
+                        y.clone(),
+                        z.clone(),
+                        port.borrow().data_type.clone(),
+                    )
+                })
                 .collect(),
             update_fn: node.update_fn.clone(),
             draw_fn: node.draw_fn.clone(),
+            state: Some(node.state.clone()),
             type_name: node.type_name.clone(),
             scalable: node.scalable,
             components: node
@@ -820,6 +1694,70 @@ impl PyNode {
                 .collect(),
             colorscheme: Some(PyColorSchemes(node.color_schemes.clone())),
             scheme: Some(node.settings.borrow().scheme.clone()),
+            menu_items: node.menu_items.clone(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_legacy_reads_both_ends() {
+        let key = ConnectionKey::parse_legacy(r#"((math.add0):("A + B"))((math.mul1):("A"))"#);
+
+        assert_eq!(
+            key,
+            Some(ConnectionKey {
+                from_node: "math.add0".to_string(),
+                from_port: "A + B".to_string(),
+                to_node: "math.mul1".to_string(),
+                to_port: "A".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_legacy_keeps_unquoted_ports() {
+        let key = ConnectionKey::parse_legacy("((a):(out))((b):(in))").unwrap();
+
+        assert_eq!(key.from_port, "out");
+        assert_eq!(key.to_port, "in");
+    }
+
+    #[test]
+    fn parse_legacy_rejects_malformed_strings() {
+        for legacy in [
+            "",
+            "((a):(\"out\"))",
+            "((a):(\"out\"))((b))",
+            "((a)(\"out\"))((b):(\"in\"))",
+        ] {
+            assert_eq!(ConnectionKey::parse_legacy(legacy), None, "{}", legacy);
+        }
+    }
+
+    #[test]
+    fn component_properties_round_trip_through_json() {
+        let cases: Vec<(Box<dyn Any>, Box<dyn Any>)> = vec![
+            (Box::new("text".to_string()), Box::new(String::new())),
+            (Box::new(0.25_f32), Box::new(0.0_f32)),
+            (Box::new(3_i32), Box::new(0_i32)),
+            (Box::new(true), Box::new(false)),
+        ];
+
+        for (property, current) in cases {
+            let value = property_to_json(property).unwrap();
+            let restored = json_to_property(&value, &*current).unwrap();
+            assert_eq!(property_to_json(restored), Some(value));
+        }
+    }
+
+    #[test]
+    fn json_to_property_keeps_the_component_type() {
+        assert!(json_to_property(&Value::from("3"), &3_i32).is_none());
+        assert!(json_to_property(&Value::from(3), &String::new()).is_none());
+        assert!(property_to_json(Box::new(Vector2::zero())).is_none());
+    }
+}
@@ -32,6 +32,9 @@ pub trait Object {
     fn update(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, _camera: &Camera) {}
     fn set_property(&mut self, key: String, value: Box<dyn Any>);
     fn get_property(&self, key: String) -> Box<dyn Any + 'static>;
+    fn persistent_properties(&self) -> Vec<&'static str> {
+        vec![]
+    }
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
@@ -554,6 +557,17 @@ impl ComboBox {
             z,
         }
     }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Ignores indices past the last option.
+    pub fn select(&mut self, index: usize) {
+        if index < self.options.len() {
+            self.selected = index;
+        }
+    }
 }
 
 impl Object for ComboBox {
@@ -672,6 +686,7 @@ impl Object for ComboBox {
 
     fn get_property(&self, key: String) -> Box<dyn Any + 'static> {
         match key.as_str() {
+            "selected" => Box::new(self.selected as i32),
             "z" => Box::new(self.z),
             _ => {
                 eprintln!("Unknown property key: {}", key);
@@ -680,8 +695,17 @@ impl Object for ComboBox {
         }
     }
 
+    fn persistent_properties(&self) -> Vec<&'static str> {
+        vec!["selected"]
+    }
+
     fn set_property(&mut self, key: String, value: Box<dyn Any + 'static>) {
         match key.as_str() {
+            "selected" => {
+                if let Ok(selected) = value.downcast::<i32>() {
+                    self.select(*selected as usize);
+                }
+            }
             "z" => {
                 if let Ok(z) = value.downcast::<i32>() {
                     self.z = *z
@@ -1180,6 +1204,7 @@ impl Object for TextBox {
             "border_color" => Box::new(self.border_color),
             "border_thickness" => Box::new(self.border_thickness),
             "text" => Box::new(self.text.clone()),
+            "active" => Box::new(self.active),
             "font_size" => Box::new(self.font_size),
             "font" => Box::new(self.font.clone()),
             "is_editable" => Box::new(self.is_editable),
@@ -1193,6 +1218,10 @@ impl Object for TextBox {
         }
     }
 
+    fn persistent_properties(&self) -> Vec<&'static str> {
+        vec!["text"]
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -1454,6 +1483,10 @@ impl Object for Slider {
         }
     }
 
+    fn persistent_properties(&self) -> Vec<&'static str> {
+        vec!["value"]
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -1580,6 +1613,10 @@ impl PyObject {
         Self { inner }
     }
 
+    pub fn inner(&self) -> Rc<RefCell<Box<dyn Object>>> {
+        self.inner.clone()
+    }
+
     pub fn to_py(py: Python, value: Box<dyn Any>) -> PyResult<Py<PyAny>> {
         for handled in [
             value.downcast_ref::<i32>().map(|v| v.into_py_any(py)),
@@ -0,0 +1,361 @@
+use raylib::prelude::Rectangle;
+use serde::{Deserialize, Serialize};
+
+use crate::structs::Vector2;
+
+/// How a wire gets from one point of its path to the next.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WireStyle {
+    #[default]
+    Bezier,
+    Straight,
+    Orthogonal,
+}
+
+impl WireStyle {
+    pub const ALL: [WireStyle; 3] = [
+        WireStyle::Bezier,
+        WireStyle::Straight,
+        WireStyle::Orthogonal,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WireStyle::Bezier => "bezier",
+            WireStyle::Straight => "straight",
+            WireStyle::Orthogonal => "orthogonal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.name() == name)
+    }
+
+    /// Steps a per-wire override through no override and then every style.
+    pub fn next_override(current: Option<WireStyle>) -> Option<WireStyle> {
+        match current {
+            None => Some(Self::ALL[0]),
+            Some(style) => Self::ALL
+                .into_iter()
+                .skip_while(|other| *other != style)
+                .nth(1),
+        }
+    }
+}
+
+/// Samples per bezier leg, the same count raylib's `DrawLineBezier` uses.
+const BEZIER_DIVISIONS: usize = 24;
+/// How far an orthogonal wire leaves a port horizontally before it may turn.
+const PORT_STUB: f32 = 20.0;
+/// Gap kept between an orthogonal wire and the nodes it goes around.
+const CLEARANCE: f32 = 12.0;
+
+/// Polyline of every leg of `path`, the geometry both drawing and hit-testing use.
+///
+/// `port_ends` tells whether the first and last points sit on an output and an input
+/// port, so orthogonal wires leave and enter them horizontally.
+pub fn route(
+    style: WireStyle,
+    path: &[Vector2],
+    port_ends: (bool, bool),
+    obstacles: &[Rectangle],
+) -> Vec<Vec<Vector2>> {
+    let last = path.len().saturating_sub(2);
+
+    path.windows(2)
+        .enumerate()
+        .map(|(i, leg)| match style {
+            WireStyle::Bezier => bezier(&leg[0], &leg[1]),
+            WireStyle::Straight => vec![leg[0].clone(), leg[1].clone()],
+            WireStyle::Orthogonal => orthogonal(
+                &leg[0],
+                &leg[1],
+                if i == 0 && port_ends.0 {
+                    PORT_STUB
+                } else {
+                    0.0
+                },
+                if i == last && port_ends.1 {
+                    PORT_STUB
+                } else {
+                    0.0
+                },
+                obstacles,
+            ),
+        })
+        .collect()
+}
+
+/// Shortest distance from `point` to the polyline through `points`.
+pub fn distance_to_polyline(points: &[Vector2], point: &Vector2) -> f32 {
+    if let [only] = points {
+        return (only.clone() - point.clone()).magnitude();
+    }
+
+    points
+        .windows(2)
+        .map(|segment| distance_to_segment(&segment[0], &segment[1], point))
+        .fold(f32::INFINITY, f32::min)
+}
+
+fn distance_to_segment(a: &Vector2, b: &Vector2, p: &Vector2) -> f32 {
+    let ab = b.clone() - a.clone();
+    let ap = p.clone() - a.clone();
+    let length2 = ab.x * ab.x + ab.y * ab.y;
+    if length2 == 0.0 {
+        return ap.magnitude();
+    }
+
+    let t = ((ap.x * ab.x + ap.y * ab.y) / length2).clamp(0.0, 1.0);
+    let projection = Vector2::new(a.x + ab.x * t, a.y + ab.y * t, None);
+    (projection - p.clone()).magnitude()
+}
+
+/// Linear in x, cubic ease-in-out in y, like raylib's `DrawLineBezier`.
+fn bezier(a: &Vector2, b: &Vector2) -> Vec<Vector2> {
+    (0..=BEZIER_DIVISIONS)
+        .map(|i| {
+            let t = i as f32 / BEZIER_DIVISIONS as f32;
+            let eased = if t < 0.5 {
+                4.0 * t * t * t
+            } else {
+                let u = 2.0 * t - 2.0;
+                0.5 * u * u * u + 1.0
+            };
+            Vector2::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * eased, None)
+        })
+        .collect()
+}
+
+/// Horizontal and vertical runs from `a` to `b` that go around `obstacles` when they can.
+///
+/// Candidates turn in a vertical channel between the ends or loop through a horizontal
+/// channel above or below; channels sit halfway or just outside a nearby node. The
+/// shortest candidate that crosses the fewest nodes wins.
+fn orthogonal(
+    a: &Vector2,
+    b: &Vector2,
+    stub_a: f32,
+    stub_b: f32,
+    obstacles: &[Rectangle],
+) -> Vec<Vector2> {
+    let start = Vector2::new(a.x + stub_a, a.y, None);
+    let end = Vector2::new(b.x - stub_b, b.y, None);
+
+    let area = Rectangle::new(
+        start.x.min(end.x) - PORT_STUB,
+        start.y.min(end.y) - PORT_STUB,
+        (start.x - end.x).abs() + PORT_STUB * 2.0,
+        (start.y - end.y).abs() + PORT_STUB * 2.0,
+    );
+    let nearby: Vec<&Rectangle> = obstacles
+        .iter()
+        .filter(|rect| rect.check_collision_recs(&area))
+        .collect();
+
+    let mut columns = vec![(start.x + end.x) / 2.0];
+    let mut rows = vec![(start.y + end.y) / 2.0];
+    for rect in &nearby {
+        columns.extend([rect.x - CLEARANCE, rect.x + rect.width + CLEARANCE]);
+        rows.extend([rect.y - CLEARANCE, rect.y + rect.height + CLEARANCE]);
+    }
+
+    let mut candidates: Vec<Vec<Vector2>> = vec![];
+    if start.x <= end.x {
+        for x in columns
+            .into_iter()
+            .filter(|x| (start.x..=end.x).contains(x))
+        {
+            candidates.push(vec![
+                a.clone(),
+                start.clone(),
+                Vector2::new(x, start.y, None),
+                Vector2::new(x, end.y, None),
+                end.clone(),
+                b.clone(),
+            ]);
+        }
+    }
+    for y in rows {
+        candidates.push(vec![
+            a.clone(),
+            start.clone(),
+            Vector2::new(start.x, y, None),
+            Vector2::new(end.x, y, None),
+            end.clone(),
+            b.clone(),
+        ]);
+    }
+
+    let best = candidates
+        .into_iter()
+        .map(|points| {
+            let crossings = points
+                .windows(2)
+                .map(|segment| {
+                    nearby
+                        .iter()
+                        .filter(|rect| crosses(&segment[0], &segment[1], rect))
+                        .count()
+                })
+                .sum::<usize>();
+            let length: f32 = points
+                .windows(2)
+                .map(|segment| (segment[1].clone() - segment[0].clone()).magnitude())
+                .sum();
+            (crossings, length, points)
+        })
+        .min_by(|x, y| x.0.cmp(&y.0).then(x.1.total_cmp(&y.1)))
+        .map(|(_, _, points)| points)
+        .unwrap_or_else(|| vec![a.clone(), b.clone()]);
+
+    simplify(best)
+}
+
+/// Whether the axis-aligned segment runs through the inside of `rect`; touching an edge,
+/// as a wire leaving a port does, does not count.
+fn crosses(a: &Vector2, b: &Vector2, rect: &Rectangle) -> bool {
+    a.x.max(b.x) > rect.x
+        && a.x.min(b.x) < rect.x + rect.width
+        && a.y.max(b.y) > rect.y
+        && a.y.min(b.y) < rect.y + rect.height
+}
+
+/// Drops repeated points and points in the middle of a straight run.
+fn simplify(points: Vec<Vector2>) -> Vec<Vector2> {
+    let mut result: Vec<Vector2> = Vec::with_capacity(points.len());
+    for point in points {
+        if result.last() == Some(&point) {
+            continue;
+        }
+        if let [.., before, last] = result.as_slice()
+            && ((before.x == last.x && last.x == point.x)
+                || (before.y == last.y && last.y == point.y))
+        {
+            result.pop();
+        }
+        result.push(point);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32) -> Vector2 {
+        Vector2::new(x, y, None)
+    }
+
+    fn axis_aligned(points: &[Vector2]) -> bool {
+        points
+            .windows(2)
+            .all(|segment| segment[0].x == segment[1].x || segment[0].y == segment[1].y)
+    }
+
+    #[test]
+    fn next_override_cycles_through_every_style() {
+        let mut current = None;
+        let mut seen = vec![];
+        for _ in 0..WireStyle::ALL.len() + 1 {
+            current = WireStyle::next_override(current);
+            seen.push(current);
+        }
+
+        assert_eq!(
+            seen,
+            vec![
+                Some(WireStyle::Bezier),
+                Some(WireStyle::Straight),
+                Some(WireStyle::Orthogonal),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for style in WireStyle::ALL {
+            assert_eq!(WireStyle::from_name(style.name()), Some(style));
+        }
+        assert_eq!(WireStyle::from_name("curvy"), None);
+    }
+
+    #[test]
+    fn route_returns_one_leg_per_segment() {
+        let path = [point(0.0, 0.0), point(50.0, 20.0), point(100.0, 0.0)];
+
+        let straight = route(WireStyle::Straight, &path, (true, true), &[]);
+        assert_eq!(
+            straight,
+            vec![
+                vec![point(0.0, 0.0), point(50.0, 20.0)],
+                vec![point(50.0, 20.0), point(100.0, 0.0)],
+            ]
+        );
+
+        let bezier = route(WireStyle::Bezier, &path, (true, true), &[]);
+        assert_eq!(bezier.len(), 2);
+        assert_eq!(bezier[0].len(), BEZIER_DIVISIONS + 1);
+        assert_eq!(bezier[0].first(), Some(&path[0]));
+        assert_eq!(bezier[0].last(), Some(&path[1]));
+    }
+
+    #[test]
+    fn orthogonal_leaves_and_enters_ports_horizontally() {
+        let legs = route(
+            WireStyle::Orthogonal,
+            &[point(100.0, 0.0), point(0.0, 50.0)],
+            (true, true),
+            &[],
+        );
+        let points = &legs[0];
+
+        assert!(axis_aligned(points));
+        assert_eq!(points[1], point(100.0 + PORT_STUB, 0.0));
+        assert_eq!(points[points.len() - 2], point(-PORT_STUB, 50.0));
+        assert_eq!(points.last(), Some(&point(0.0, 50.0)));
+    }
+
+    #[test]
+    fn orthogonal_goes_around_nodes_in_the_way() {
+        let node = Rectangle::new(80.0, -20.0, 40.0, 40.0);
+        let points = orthogonal(&point(0.0, 0.0), &point(200.0, 0.0), 0.0, 0.0, &[node]);
+
+        assert!(axis_aligned(&points));
+        assert!(
+            points
+                .windows(2)
+                .all(|segment| !crosses(&segment[0], &segment[1], &node))
+        );
+        assert_eq!(points.first(), Some(&point(0.0, 0.0)));
+        assert_eq!(points.last(), Some(&point(200.0, 0.0)));
+    }
+
+    #[test]
+    fn simplify_drops_repeated_and_collinear_points() {
+        let points = simplify(vec![
+            point(0.0, 0.0),
+            point(0.0, 0.0),
+            point(10.0, 0.0),
+            point(20.0, 0.0),
+            point(20.0, 10.0),
+            point(20.0, 30.0),
+        ]);
+
+        assert_eq!(
+            points,
+            vec![point(0.0, 0.0), point(20.0, 0.0), point(20.0, 30.0)]
+        );
+    }
+
+    #[test]
+    fn distance_to_polyline_measures_to_the_nearest_segment() {
+        let points = [point(0.0, 0.0), point(10.0, 0.0), point(10.0, 10.0)];
+
+        assert_eq!(distance_to_polyline(&points, &point(5.0, 3.0)), 3.0);
+        assert_eq!(distance_to_polyline(&points, &point(14.0, 5.0)), 4.0);
+        assert_eq!(distance_to_polyline(&points[..1], &point(3.0, 4.0)), 5.0);
+    }
+}
@@ -0,0 +1,92 @@
+use std::{collections::BTreeMap, fs};
+
+use pyo3::prelude::*;
+use serde_json::Value;
+
+use crate::{
+    graph,
+    node::{Node, json_to_py, py_to_json},
+    save::SaveFile,
+    window::{EDITOR_STATE, Window},
+};
+
+type PortValues = BTreeMap<String, BTreeMap<String, Value>>;
+
+pub fn run(project_path: &str, inputs_path: Option<&str>) -> Result<(), String> {
+    let mut window = Window::new();
+    window.init_resources();
+
+    let mut save = SaveFile::from_file(project_path)?;
+    /* Notes and frames take no part in evaluation and need a font to exist */
+    save.annotations.clear();
+
+    let inputs: PortValues = match inputs_path {
+        Some(path) => {
+            let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            serde_json::from_str(&content).map_err(|e| format!("{}: {}", path, e))?
+        }
+        None => BTreeMap::new(),
+    };
+
+    let outputs = EDITOR_STATE.with_borrow_mut(|state| -> Result<PortValues, String> {
+        window.load_from_save(save, state);
+
+        Python::attach(|py| -> Result<(), String> {
+            for (node_id, ports) in &inputs {
+                for (label, value) in ports {
+                    let port = window
+                        .find_port(node_id, label)
+                        .ok_or_else(|| format!("Unknown port '{}' on node '{}'", label, node_id))?;
+                    let value = json_to_py(py, value).map_err(|e| e.to_string())?;
+                    port.borrow_mut().write(value);
+                }
+            }
+            Ok(())
+        })?;
+
+        graph::evaluate(&window.objects, &state.connections);
+        state.evaluation_requested = false;
+
+        Ok(collect_outputs(&window))
+    })?;
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&outputs).map_err(|e| e.to_string())?
+    );
+
+    Ok(())
+}
+
+/// Output values that have no JSON form are written as their `str()`, or `null` failing that.
+fn collect_outputs(window: &Window) -> PortValues {
+    Python::attach(|py| {
+        let mut outputs = BTreeMap::new();
+
+        for (id, obj) in &window.objects {
+            let obj = obj.borrow();
+            let Some(node) = obj.as_any().downcast_ref::<Node>() else {
+                continue;
+            };
+
+            let mut ports = BTreeMap::new();
+            for (label, is_output, _, port) in &node.ports {
+                if *is_output {
+                    let value = port.borrow().read(py);
+                    let value = py_to_json(py, value.bind(py)).unwrap_or_else(|e| {
+                        eprintln!("Output '{}' of node '{}' is not JSON: {}", label, id, e);
+                        value
+                            .bind(py)
+                            .str()
+                            .map(|text| Value::String(text.to_string()))
+                            .unwrap_or(Value::Null)
+                    });
+                    ports.insert(label.clone(), value);
+                }
+            }
+            outputs.insert(id.clone(), ports);
+        }
+
+        outputs
+    })
+}
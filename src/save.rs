@@ -1,15 +1,100 @@
-use std::{cell::RefCell, collections::HashMap, fs, num::NonZeroIsize, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    num::NonZeroIsize,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use rfd::FileDialog;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::node::Node;
+use crate::{
+    annotation::{Annotation, AnnotationKind},
+    node::{ComponentValues, ConnectionKey, Node, NodeState},
+    routing::WireStyle,
+    settings,
+};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct NodeSave {
     pub id: String,
     pub type_name: String,
     pub position: [f32; 2],
+    #[serde(default, skip_serializing_if = "ComponentValues::is_empty")]
+    pub components: ComponentValues,
+    #[serde(default, skip_serializing_if = "NodeState::is_empty")]
+    pub state: NodeState,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bypassed: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub collapsed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+impl NodeSave {
+    pub fn from_node(node: &Node) -> Self {
+        if let Some(missing) = &node.missing {
+            return NodeSave {
+                position: node.position.clone().into(),
+                collapsed: node.collapsed,
+                title: node.title.clone(),
+                color: node.color.clone(),
+                ..missing.clone()
+            };
+        }
+
+        NodeSave {
+            id: node.id.clone(),
+            type_name: node.type_name.clone(),
+            position: node.position.clone().into(),
+            components: node.component_values(),
+            state: node.state_values(),
+            bypassed: node.bypassed,
+            collapsed: node.collapsed,
+            title: node.title.clone(),
+            color: node.color.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AnnotationSave {
+    pub id: String,
+    pub kind: AnnotationKind,
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    #[serde(default)]
+    pub text: String,
+}
+
+impl AnnotationSave {
+    pub fn from_annotation(annotation: &Annotation) -> Self {
+        AnnotationSave {
+            id: annotation.id.clone(),
+            kind: annotation.kind,
+            position: annotation.position.clone().into(),
+            size: annotation.size.clone().into(),
+            text: annotation.text.clone(),
+        }
+    }
+}
+
+/// Reroute points and routing override of one connection, listed separately so
+/// `ConnectionKey` stays a plain key.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WireSave {
+    #[serde(flatten)]
+    pub connection: ConnectionKey,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub points: Vec<[f32; 2]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<WireStyle>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -18,20 +103,81 @@ pub struct CameraSave {
     pub zoom: f32,
 }
 
+/// Where the editor keeps unsaved work until it is saved or discarded.
+fn recovery_dir() -> Option<PathBuf> {
+    settings::user_data_dir().map(|dir| dir.join("recovery"))
+}
+
+/// Recovery file of the project saved at `project_path`, or of untitled work for `None`.
+pub fn recovery_path(project_path: Option<&str>) -> Option<String> {
+    recovery_dir().map(|dir| {
+        dir.join(recovery_file_name(project_path))
+            .to_string_lossy()
+            .to_string()
+    })
+}
+
+/// The newest recovery file left behind by any project, offered on start.
+pub fn latest_recovery() -> Option<String> {
+    fs::read_dir(recovery_dir()?)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "dnode"))
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path.to_string_lossy().to_string())
+}
+
+/// Projects sharing a file name in different folders get different files through the path hash.
+fn recovery_file_name(project_path: Option<&str>) -> String {
+    let Some(project_path) = project_path else {
+        return "untitled.dnode".to_string();
+    };
+    let stem = Path::new(project_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    /* FNV-1a, which unlike DefaultHasher keeps its output across toolchains */
+    let hash = project_path
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    format!("{}-{:016x}.dnode", stem, hash)
+}
+
+/// Bump together with a new entry in `MIGRATIONS` whenever the document layout changes.
+pub const FORMAT_VERSION: u32 = 2;
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// `MIGRATIONS[n]` upgrades a version `n + 1` document to version `n + 2`.
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2];
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct SaveFile {
+    pub format_version: u32,
     pub project_name: String,
     pub nodes: Vec<NodeSave>,
-    pub connections: Vec<String>,
+    pub connections: Vec<ConnectionKey>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<AnnotationSave>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wires: Vec<WireSave>,
     pub camera: CameraSave,
 }
 
 impl SaveFile {
     pub fn new(project_name: String) -> Self {
         SaveFile {
+            format_version: FORMAT_VERSION,
             project_name,
             nodes: Vec::new(),
             connections: Vec::new(),
+            annotations: Vec::new(),
+            wires: Vec::new(),
             camera: CameraSave {
                 position: [0.0, 0.0],
                 zoom: 1.0,
@@ -42,47 +188,239 @@ impl SaveFile {
     pub fn from(
         project_name: String,
         nodes: Vec<NodeSave>,
-        connections: Vec<String>,
+        connections: Vec<ConnectionKey>,
         camera: CameraSave,
     ) -> Self {
         SaveFile {
+            format_version: FORMAT_VERSION,
             project_name,
             nodes,
             connections,
+            annotations: Vec::new(),
+            wires: Vec::new(),
             camera,
         }
     }
 
-    pub fn read() -> Option<Self> {
-        if let Some(path) = FileDialog::new()
+    /// Returns `None` when the dialog is cancelled.
+    pub fn open_dialog() -> Option<String> {
+        FileDialog::new()
             .set_title("Open Project")
             .add_filter("DNODE File", &["dnode"])
             .set_file_name("untitled.dnode")
             .pick_file()
-        {
-            let content = fs::read_to_string(&path).expect("Dosya okunamadı");
-            serde_json::from_str::<SaveFile>(&content).ok()
-        } else {
-            None
-        }
-    }
-
-    pub fn from_file(path: &str) -> Option<Self> {
-        let content = fs::read_to_string(&path).expect("Dosya okunamadı");
-        serde_json::from_str::<SaveFile>(&content).ok()
+            .map(|path| path.to_string_lossy().to_string())
     }
 
-    pub fn write(&self) -> bool {
+    /// Returns `None` when the dialog is cancelled.
+    pub fn save_dialog(project_name: &str) -> Option<String> {
         FileDialog::new()
             .set_title("Save Project As")
             .add_filter("DNODE File", &["dnode"])
-            .set_file_name(&format!("{}.dnode", self.project_name))
+            .set_file_name(&format!("{}.dnode", project_name))
             .save_file()
-            .map(|path| {
-                std::fs::write(&path, serde_json::to_string_pretty(self).unwrap())
-                    .expect("Failed to write save file");
-                true
-            })
-            .unwrap_or(false)
+            .map(|path| path.to_string_lossy().to_string())
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&content).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut document: Value = serde_json::from_str(content)
+            .map_err(|e| format!("not a valid project file ({})", e))?;
+
+        /* Files written before versioning have no format_version and count as version 1 */
+        let version = match document.get("format_version") {
+            None => 1,
+            Some(value) => value
+                .as_u64()
+                .map(|v| v as u32)
+                .ok_or_else(|| format!("format_version must be a number, found {}", value))?,
+        };
+
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(format!(
+                "format version {} is not supported (this build reads versions 1 to {})",
+                version, FORMAT_VERSION
+            ));
+        }
+
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+            migration(&mut document)
+                .map_err(|e| format!("upgrading from format version {} failed: {}", i + 1, e))?;
+        }
+
+        if let Some(object) = document.as_object_mut() {
+            object.insert("format_version".to_string(), Value::from(FORMAT_VERSION));
+        }
+
+        serde_json::from_value(document).map_err(|e| {
+            format!(
+                "format version {} document is invalid: {}",
+                FORMAT_VERSION, e
+            )
+        })
+    }
+
+    pub fn write_to(&self, path: &str) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+/// Version 1 stored connections as `((node):("port"))((node):("port"))` strings.
+fn migrate_v1_to_v2(document: &mut Value) -> Result<(), String> {
+    let Some(connections) = document
+        .get_mut("connections")
+        .and_then(Value::as_array_mut)
+    else {
+        return Ok(());
+    };
+
+    for (i, connection) in connections.iter_mut().enumerate() {
+        if let Value::String(legacy) = connection {
+            let key = ConnectionKey::parse_legacy(legacy)
+                .ok_or_else(|| format!("connections[{}] '{}' could not be parsed", i, legacy))?;
+            *connection = serde_json::to_value(key).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1_DOCUMENT: &str = r#"{
+        "project_name": "old",
+        "nodes": [{"id": "math.add0", "type_name": "math.add", "position": [10.0, 20.0]}],
+        "connections": ["((math.add0):(\"A + B\"))((math.add1):(\"A\"))"],
+        "camera": {"position": [0.0, 0.0], "zoom": 1.0}
+    }"#;
+
+    #[test]
+    fn parse_migrates_version_1_connections() {
+        let save = SaveFile::parse(V1_DOCUMENT).unwrap();
+
+        assert_eq!(save.format_version, FORMAT_VERSION);
+        assert_eq!(save.project_name, "old");
+        assert_eq!(save.nodes[0].position, [10.0, 20.0]);
+        assert_eq!(
+            save.connections,
+            vec![ConnectionKey {
+                from_node: "math.add0".to_string(),
+                from_port: "A + B".to_string(),
+                to_node: "math.add1".to_string(),
+                to_port: "A".to_string(),
+            }]
+        );
+        assert!(save.annotations.is_empty());
+        assert!(save.wires.is_empty());
+    }
+
+    #[test]
+    fn parse_round_trips_the_current_version() {
+        let mut save = SaveFile::new("current".to_string());
+        save.connections.push(ConnectionKey {
+            from_node: "a".to_string(),
+            from_port: "out".to_string(),
+            to_node: "b".to_string(),
+            to_port: "in".to_string(),
+        });
+        save.camera.zoom = 2.0;
+
+        let parsed = SaveFile::parse(&serde_json::to_string(&save).unwrap()).unwrap();
+
+        assert_eq!(parsed.format_version, FORMAT_VERSION);
+        assert_eq!(parsed.connections, save.connections);
+        assert_eq!(parsed.camera.zoom, 2.0);
+    }
+
+    #[test]
+    fn parse_rejects_unsupported_versions() {
+        for version in [0, FORMAT_VERSION + 1] {
+            let document = format!(
+                r#"{{"format_version": {}, "project_name": "p", "nodes": [], "connections": [],
+                    "camera": {{"position": [0.0, 0.0], "zoom": 1.0}}}}"#,
+                version
+            );
+
+            let error = SaveFile::parse(&document).unwrap_err();
+            assert!(error.contains("is not supported"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_a_non_numeric_version() {
+        let error = SaveFile::parse(r#"{"format_version": "2"}"#).unwrap_err();
+
+        assert!(error.contains("must be a number"), "{}", error);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_json() {
+        let error = SaveFile::parse("{ not json").unwrap_err();
+
+        assert!(error.starts_with("not a valid project file"), "{}", error);
+    }
+
+    #[test]
+    fn parse_reports_a_failed_migration() {
+        let document = V1_DOCUMENT.replace("))((", ")(");
+
+        let error = SaveFile::parse(&document).unwrap_err();
+        assert!(
+            error.starts_with("upgrading from format version 1 failed"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn parse_reports_an_invalid_current_document() {
+        let document = format!(
+            r#"{{"format_version": {}, "project_name": "p"}}"#,
+            FORMAT_VERSION
+        );
+
+        let error = SaveFile::parse(&document).unwrap_err();
+
+        assert!(error.contains("document is invalid"), "{}", error);
+    }
+
+    #[test]
+    fn recovery_file_name_is_keyed_by_project_path() {
+        let first = recovery_file_name(Some("/work/a/graph.dnode"));
+        let second = recovery_file_name(Some("/work/b/graph.dnode"));
+
+        assert!(first.starts_with("graph-") && first.ends_with(".dnode"));
+        assert_ne!(first, second);
+        assert_eq!(first, recovery_file_name(Some("/work/a/graph.dnode")));
+        assert_eq!(recovery_file_name(None), "untitled.dnode");
+    }
+
+    #[test]
+    fn component_values_survive_a_save_and_load() {
+        let edited: NodeSave = serde_json::from_str(
+            r#"{"id": "text.input0", "type_name": "text.input", "position": [0.0, 0.0],
+                "components": {"name": {"text": "hello"}, "amount": {"value": 0.5}}}"#,
+        )
+        .unwrap();
+        let untouched: NodeSave = serde_json::from_str(
+            r#"{"id": "math.add0", "type_name": "math.add", "position": [0.0, 0.0]}"#,
+        )
+        .unwrap();
+
+        let content = serde_json::to_string(&[&edited, &untouched]).unwrap();
+        let loaded: Vec<NodeSave> = serde_json::from_str(&content).unwrap();
+
+        assert_eq!(loaded[0].components, edited.components);
+        assert_eq!(loaded[0].components["name"]["text"], "hello");
+        assert_eq!(loaded[0].components["amount"]["value"], 0.5);
+        assert!(loaded[1].components.is_empty());
+        assert_eq!(content.matches("\"components\"").count(), 1);
     }
 }
@@ -1,7 +1,9 @@
-use std::fs;
+use std::{env, fs, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use crate::{routing::WireStyle, structs::Vector2};
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Settings {
     pub language: String,
@@ -9,13 +11,105 @@ pub struct Settings {
     pub font: String,
     pub grid_size: [f32; 2],
     pub grid_square_size: [f32; 2],
+    /// Seconds between recovery saves, 0 turns autosave off.
+    #[serde(default = "default_autosave_interval")]
+    pub autosave_interval: f32,
+    #[serde(default)]
+    pub recent_files: Vec<String>,
+    /// Dragged nodes land on grid corners unless Alt is held.
+    #[serde(default)]
+    pub snap_to_grid: bool,
+    /// Node sizes are rounded up to whole grid squares.
+    #[serde(default)]
+    pub snap_sizes: bool,
+    /// Routing of wires that have no style of their own.
+    #[serde(default)]
+    pub wire_style: WireStyle,
+}
+
+/// Values changed from inside the editor, kept per user instead of in the shipped settings.toml.
+#[derive(Serialize)]
+struct UserSettings<'a> {
+    recent_files: &'a [String],
+    snap_to_grid: bool,
+    wire_style: WireStyle,
+}
+
+fn default_autosave_interval() -> f32 {
+    60.0
+}
+
+/// Directory under the platform's per-user base, e.g. `~/.local/share/doranode`.
+/// `None` when the environment names no home directory.
+fn user_dir(xdg_var: &str, home_fallback: &str) -> Option<PathBuf> {
+    let base = env::var_os(xdg_var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(home_fallback)))?;
+
+    Some(base.join("doranode"))
+}
+
+pub fn user_config_dir() -> Option<PathBuf> {
+    user_dir("XDG_CONFIG_HOME", ".config")
+}
+
+pub fn user_data_dir() -> Option<PathBuf> {
+    user_dir("XDG_DATA_HOME", ".local/share")
 }
 
 impl Settings {
+    /// Reads the shipped defaults at `path`, overridden by the per-user settings file if present.
     pub fn load_settings(path: &str) -> Settings {
         let data = fs::read_to_string(path).expect("Ayar dosyası okunamadı!");
+        let mut table = toml::from_str::<toml::Table>(&data).expect("TOML parse hatası!");
+
+        if let Some(user_path) = Self::user_settings_path()
+            && let Ok(user_data) = fs::read_to_string(&user_path)
+        {
+            match toml::from_str::<toml::Table>(&user_data) {
+                Ok(user_table) => {
+                    for (key, value) in user_table {
+                        table.insert(key, value);
+                    }
+                }
+                Err(e) => eprintln!("{}: {}", user_path.display(), e),
+            }
+        }
+
+        toml::Value::Table(table)
+            .try_into::<Settings>()
+            .expect("TOML parse hatası!")
+    }
+
+    fn user_settings_path() -> Option<PathBuf> {
+        user_config_dir().map(|dir| dir.join("settings.toml"))
+    }
+
+    /// Writes the values in `UserSettings` to the per-user settings file.
+    pub fn save_user_settings(&self) -> Result<(), String> {
+        let path = Self::user_settings_path().ok_or("no per-user config directory")?;
+        let user_settings = UserSettings {
+            recent_files: &self.recent_files,
+            snap_to_grid: self.snap_to_grid,
+            wire_style: self.wire_style,
+        };
+        let toml_string = toml::to_string_pretty(&user_settings).map_err(|e| e.to_string())?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        fs::write(&path, toml_string).map_err(|e| format!("{}: {}", path.display(), e))
+    }
 
-        toml::from_str::<Settings>(&data).expect("TOML parse hatası!")
+    pub fn snap_position(&self, position: Vector2) -> Vector2 {
+        let [width, height] = self.grid_square_size;
+        Vector2::new(
+            (position.x / width).round() * width,
+            (position.y / height).round() * height,
+            None,
+        )
     }
 
     pub fn save_settings(path: &str, settings: &Settings) {
@@ -30,7 +30,6 @@ impl Translations {
     pub fn load_from_file(&mut self, file_contents: String, language: &str) -> &mut Self {
         let parsed: TranslationFile =
             serde_json::from_str(&file_contents).expect("Couldn't parse translation JSON");
-        println!("{:?}", self);
 
         if let Some(nodes) = parsed.nodes {
             self.node_translations
@@ -1,15 +1,27 @@
 use pyo3::prelude::*;
 use raylib::prelude::*;
 use raylib_sys::SetTextureFilter;
-use std::{cell::RefCell, collections::HashMap, fs, path::Path, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::Path,
+    rc::Rc,
+};
 
 use crate::{
+    annotation::{Annotation, AnnotationKind, DOUBLE_CLICK_TIME},
     colorscheme::ColorSchemes,
+    graph::{self, LayoutNode},
     gui::{self, Dialog, DialogButton, ToolBarItem},
+    history::{Command, History},
     modules::ModuleManager,
-    node::{Connection, Node, Port},
+    node::{Connection, ConnectionKey, NODE_COLORS, Node, Port, types_compatible},
     objects::{Camera, Grid, Object, register_object_types},
-    save::{CameraSave, NodeSave, SaveFile},
+    routing::{self, WireStyle},
+    save::{
+        AnnotationSave, CameraSave, NodeSave, SaveFile, WireSave, latest_recovery, recovery_path,
+    },
     settings::Settings,
     structs::Vector2,
     translations::Translations,
@@ -18,13 +30,28 @@ use crate::{
 pub struct EditorState {
     pub dragging_from: Option<Rc<RefCell<Box<Port>>>>,
     pub dragging_to: Option<Rc<RefCell<Box<Port>>>>,
-    pub connections: HashMap<String, Connection>,
+    pub connections: HashMap<ConnectionKey, Connection>,
     pub node_names: HashMap<String, Vec<usize>>,
     pub selected_module: Option<String>,
     pub dialog: Option<Dialog>,
     pub save_file: Option<SaveFile>,
     pub project_name: String,
+    pub project_path: Option<String>,
+    pub pending_open: Option<String>,
+    /// Recovery file offered on start, possibly left behind by another project.
+    pub pending_recovery: Option<String>,
+    pub exit_requested: bool,
     pub selector_size: Vector2,
+    pub evaluation_requested: bool,
+    pub history: History,
+    pub dirty: bool,
+    pub autosave_pending: bool,
+    pub last_autosave: f64,
+    pub selection: BTreeSet<String>,
+    /// World-space corners of the rubber band while it is being dragged.
+    pub marquee: Option<(Vector2, Vector2)>,
+    /// Reroute points and styles of removed connections, given back if an undo reconnects them.
+    pub detached_wires: HashMap<ConnectionKey, WireSave>,
 }
 
 thread_local! {
@@ -37,7 +64,19 @@ thread_local! {
         dialog: None,
         save_file: None,
         project_name: "untitled".to_string(),
+        project_path: None,
+        pending_open: None,
+        pending_recovery: None,
+        exit_requested: false,
         selector_size: Vector2::zero(),
+        evaluation_requested: true,
+        history: History::new(),
+        dirty: false,
+        autosave_pending: false,
+        last_autosave: 0.0,
+        selection: BTreeSet::new(),
+        marquee: None,
+        detached_wires: HashMap::new(),
     });
 }
 pub struct Window {
@@ -54,8 +93,62 @@ pub struct Window {
     pub module_manager: Rc<RefCell<ModuleManager>>,
     pub node_selector: Option<gui::NodeSelector>,
     pub tool_bar: Option<gui::ToolBar>,
+    pub minimap: Option<gui::Minimap>,
+    pub node_palette: Option<gui::NodePalette>,
+    node_drag: Option<NodeDrag>,
+    wire_drop: Option<WireDrop>,
+    reroute_drag: Option<RerouteDrag>,
+    /// Wire clicked last and when, to tell a double click apart.
+    last_wire_click: Option<(ConnectionKey, f64)>,
+    pub context_menu: Option<gui::ContextMenu>,
+    context_target: Option<ContextTarget>,
+}
+
+/// What the open context menu acts on, with the world position it was opened at.
+enum ContextTarget {
+    Node {
+        id: String,
+        at: Vector2,
+    },
+    Wire {
+        key: ConnectionKey,
+        segment: usize,
+        reroute: Option<usize>,
+        at: Vector2,
+    },
+    Annotation {
+        id: String,
+    },
+    Canvas {
+        at: Vector2,
+    },
+}
+
+/// Port a wire was dropped from onto empty canvas, waiting for the node picker.
+struct WireDrop {
+    node: String,
+    port: String,
+    is_output: bool,
+    data_type: String,
+}
+
+/// Positions of every node moving with the one under the mouse, taken when the drag started.
+struct NodeDrag {
+    leader: String,
+    origins: Vec<(String, Vector2)>,
+    /// Mouse offset from the leader when the window moves it itself, as for annotations.
+    grab: Option<Vector2>,
+}
+
+/// Reroute point under the mouse and the connection's points before it moved.
+struct RerouteDrag {
+    key: ConnectionKey,
+    index: usize,
+    before: Vec<Vector2>,
 }
 
+const RECENT_FILES_LIMIT: usize = 8;
+
 const TURKISH_ALPHABET: &str = " ABCDEFGHIİJKLMNOÖPRSŞTUÜVYZQWXYZabcdefghijklmnopqrstuvwxyzçğıöşüÇĞİÖŞÜ0123456789!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~√";
 
 impl Window {
@@ -79,10 +172,18 @@ impl Window {
             module_manager: Rc::new(RefCell::new(ModuleManager::new())),
             node_selector: None,
             tool_bar: None,
+            minimap: None,
+            node_palette: None,
+            node_drag: None,
+            wire_drop: None,
+            reroute_drag: None,
+            last_wire_click: None,
+            context_menu: None,
+            context_target: None,
         }
     }
 
-    pub fn init(&mut self) -> (RaylibHandle, RaylibThread) {
+    pub fn init_resources(&mut self) {
         register_object_types();
         Python::initialize();
         self.load_translations();
@@ -91,6 +192,10 @@ impl Window {
             Path::new("resources/doranode-builtin.dnode"),
             &mut self.translations.borrow_mut(),
         );
+    }
+
+    pub fn init(&mut self) -> (RaylibHandle, RaylibThread) {
+        self.init_resources();
 
         let (mut rl_handle, rl_thread) = raylib::init().size(960, 720).resizable().build();
         rl_handle.set_window_title(
@@ -120,6 +225,13 @@ impl Window {
             self.settings.clone(),
             self.translations.clone(),
         ));
+        self.node_palette = Some(gui::NodePalette::new(
+            self.module_manager.clone(),
+            self.active_font.clone().unwrap(),
+            self.color_schemes.clone(),
+            self.settings.clone(),
+            self.translations.clone(),
+        ));
 
         let mut tool_bar = gui::ToolBar::new(
             self.color_schemes.clone(),
@@ -134,36 +246,273 @@ impl Window {
                 ToolBarItem {
                     label: "new".to_string(),
                     on_click: Some("new_file".to_string()),
+                    text: None,
                     children: vec![],
                     expanded: false,
                 },
                 ToolBarItem {
                     label: "open".to_string(),
                     on_click: Some("open_file".to_string()),
+                    text: None,
                     children: vec![],
                     expanded: false,
                 },
                 ToolBarItem {
                     label: "save".to_string(),
                     on_click: Some("save_file".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "save_as".to_string(),
+                    on_click: Some("save_file_as".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "recent".to_string(),
+                    on_click: None,
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "exit".to_string(),
+                    on_click: Some("exit".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+            ],
+            text: None,
+            on_click: None,
+            expanded: false,
+        });
+
+        tool_bar.add_item(ToolBarItem {
+            label: "edit".to_string(),
+            children: vec![
+                ToolBarItem {
+                    label: "undo".to_string(),
+                    on_click: Some("undo".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "redo".to_string(),
+                    on_click: Some("redo".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "cut".to_string(),
+                    on_click: Some("cut".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "copy".to_string(),
+                    on_click: Some("copy".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "paste".to_string(),
+                    on_click: Some("paste".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "duplicate".to_string(),
+                    on_click: Some("duplicate".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+            ],
+            text: None,
+            on_click: None,
+            expanded: false,
+        });
+
+        tool_bar.add_item(ToolBarItem {
+            label: "insert".to_string(),
+            children: vec![
+                ToolBarItem {
+                    label: "note".to_string(),
+                    on_click: Some("add_note".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "frame".to_string(),
+                    on_click: Some("add_frame".to_string()),
+                    text: None,
                     children: vec![],
                     expanded: false,
                 },
             ],
+            text: None,
+            on_click: None,
+            expanded: false,
+        });
+
+        tool_bar.add_item(ToolBarItem {
+            label: "arrange".to_string(),
             on_click: None,
+            text: None,
+            children: vec![
+                ToolBarItem {
+                    label: "auto_arrange".to_string(),
+                    on_click: Some("auto_arrange".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "snap_to_grid".to_string(),
+                    on_click: Some("toggle_snap".to_string()),
+                    text: None,
+                    children: vec![],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "wire_style".to_string(),
+                    on_click: None,
+                    text: None,
+                    children: WireStyle::ALL
+                        .into_iter()
+                        .map(|style| ToolBarItem {
+                            label: style.name().to_string(),
+                            on_click: Some(format!("wire_style:{}", style.name())),
+                            text: None,
+                            children: vec![],
+                            expanded: false,
+                        })
+                        .collect(),
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "align".to_string(),
+                    on_click: None,
+                    text: None,
+                    children: vec![
+                        ToolBarItem {
+                            label: "left".to_string(),
+                            on_click: Some("align_left".to_string()),
+                            text: None,
+                            children: vec![],
+                            expanded: false,
+                        },
+                        ToolBarItem {
+                            label: "right".to_string(),
+                            on_click: Some("align_right".to_string()),
+                            text: None,
+                            children: vec![],
+                            expanded: false,
+                        },
+                        ToolBarItem {
+                            label: "top".to_string(),
+                            on_click: Some("align_top".to_string()),
+                            text: None,
+                            children: vec![],
+                            expanded: false,
+                        },
+                        ToolBarItem {
+                            label: "bottom".to_string(),
+                            on_click: Some("align_bottom".to_string()),
+                            text: None,
+                            children: vec![],
+                            expanded: false,
+                        },
+                    ],
+                    expanded: false,
+                },
+                ToolBarItem {
+                    label: "distribute".to_string(),
+                    on_click: None,
+                    text: None,
+                    children: vec![
+                        ToolBarItem {
+                            label: "horizontal".to_string(),
+                            on_click: Some("distribute_horizontal".to_string()),
+                            text: None,
+                            children: vec![],
+                            expanded: false,
+                        },
+                        ToolBarItem {
+                            label: "vertical".to_string(),
+                            on_click: Some("distribute_vertical".to_string()),
+                            text: None,
+                            children: vec![],
+                            expanded: false,
+                        },
+                    ],
+                    expanded: false,
+                },
+            ],
             expanded: false,
         });
 
         self.tool_bar = Some(tool_bar);
+        self.context_menu = Some(gui::ContextMenu::new(
+            self.color_schemes.clone(),
+            self.settings.clone(),
+            self.active_font.clone().unwrap(),
+            self.translations.clone(),
+        ));
+        self.minimap = Some(gui::Minimap::new(
+            self.color_schemes.clone(),
+            self.settings.clone(),
+        ));
+        self.refresh_recent_menu();
+
+        if let Some(path) = latest_recovery() {
+            EDITOR_STATE.with_borrow_mut(|state| {
+                state.pending_recovery = Some(path);
+                state.dialog = Some(Dialog::new(
+                    "file.recover".to_string(),
+                    vec![
+                        (DialogButton::Yes, "file.recover".to_string()),
+                        (DialogButton::No, "file.discard_recovery".to_string()),
+                    ],
+                    self.color_schemes.clone(),
+                    self.settings.clone(),
+                    self.translations.clone(),
+                    self.active_font.clone().unwrap(),
+                ))
+            });
+        }
 
         (rl_handle, rl_thread)
     }
 
     pub fn run(&mut self, mut rl_handle: RaylibHandle, rl_thread: RaylibThread) {
-        while !rl_handle.window_should_close() {
+        while !EDITOR_STATE.with_borrow(|state| state.exit_requested) {
+            /* The close button asks about unsaved changes like the Exit menu item */
+            if rl_handle.window_should_close() {
+                self.exit();
+            }
             self.update(&mut rl_handle, &rl_thread);
             self.draw(&mut rl_handle, &rl_thread);
         }
+
+        /* Unsaved work stays in the recovery file and is offered on the next start */
+        EDITOR_STATE.with_borrow_mut(|state| {
+            if state.dirty {
+                self.autosave(state);
+            } else {
+                Self::discard_recovery(state.project_path.as_deref());
+            }
+        });
     }
 
     fn update(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
@@ -184,6 +533,60 @@ impl Window {
             self.last_mouse = mouse.clone();
         }
 
+        if let Some(minimap) = &mut self.minimap {
+            minimap.update(rl, thread, &cam);
+            if let Some(target) = minimap.jump_to.take() {
+                cam.target = target;
+            }
+            if minimap.dragging {
+                self.dragging = false;
+            }
+        }
+        let screen = Vector2::new(
+            rl.get_screen_width() as f32,
+            rl.get_screen_height() as f32,
+            None,
+        );
+        let over_minimap = self.minimap.as_ref().is_some_and(|minimap| {
+            minimap.dragging
+                || (self.node_drag.is_none()
+                    && minimap
+                        .area(screen.clone())
+                        .check_collision_point_rec(mouse.clone()))
+        });
+
+        /* A click while the context menu is open only closes it or picks an entry */
+        let menu_was_open = self.context_menu.as_ref().is_some_and(|menu| menu.open);
+        let mut context_events: Vec<String> = vec![];
+        if let Some(menu) = &mut self.context_menu {
+            menu.update(rl, thread, &cam);
+            context_events = std::mem::take(&mut menu.events);
+        }
+        if menu_was_open {
+            self.dragging = false;
+        }
+        /* Presses on the minimap, the palette or the context menu must not reach nodes below */
+        let canvas_input = !over_minimap
+            && !menu_was_open
+            && !self
+                .node_palette
+                .as_ref()
+                .is_some_and(|palette| palette.open);
+        let mut context_request: Option<ContextTarget> = None;
+
+        let editing_text = self.objects.values().any(|obj| {
+            let obj = obj.borrow();
+            obj.as_any()
+                .downcast_ref::<Node>()
+                .is_some_and(Node::editing_text)
+                || obj
+                    .as_any()
+                    .downcast_ref::<Annotation>()
+                    .is_some_and(|annotation| annotation.editing)
+        });
+        let mut node_request: Option<(String, Vector2, Option<WireDrop>)> = None;
+        let mut wire_drop: Option<(WireDrop, Vector2)> = None;
+
         EDITOR_STATE.with(|editor_state| {
             let mut state = editor_state.borrow_mut();
 
@@ -193,6 +596,11 @@ impl Window {
                 && state.dragging_to.is_none()
                 && state.selected_module.is_none()
                 && state.dialog.is_none()
+                && state.marquee.is_none()
+                && !self
+                    .node_palette
+                    .as_ref()
+                    .is_some_and(|palette| palette.open)
             {
                 let delta = mouse.clone() - self.last_mouse.clone();
                 cam.target = cam.target.clone() - (delta / cam.zoom).into();
@@ -205,100 +613,206 @@ impl Window {
                     && !Rectangle::new(0.0, 0.0, state.selector_size.x, state.selector_size.y)
                         .check_collision_point_rec(mouse.clone())
                 {
-                    let list = state
-                        .node_names
-                        .entry(module.clone())
-                        .or_insert_with(Vec::new);
-
-                    let next_index = if list.is_empty() {
-                        1
-                    } else {
-                        Window::missing_numbers(list)
-                            .first()
-                            .cloned()
-                            .unwrap_or_else(|| list.iter().max().cloned().unwrap() + 1)
-                    };
-
-                    let id = format!("{}{}", module, next_index);
-
-                    if let Some(node) = self.module_manager.borrow().generate(
-                        Vector2::zero(),
-                        module,
-                        self.active_font.clone().unwrap(),
-                        self.translations.clone(),
-                        self.color_schemes.clone(),
-                        self.settings.clone(),
-                        id.clone(),
-                    ) {
-                        let mouse_world: Vector2 =
-                            rl.get_screen_to_world2D(mouse.clone(), &cam.clone()).into();
-                        let size = node.borrow().size.clone();
-                        node.borrow_mut().position = mouse_world - size / 2.0;
-
-                        self.objects.insert(id.clone(), node.clone());
-
-                        list.push(next_index);
-                    }
+                    let mouse_world: Vector2 =
+                        rl.get_screen_to_world2D(mouse.clone(), &cam.clone()).into();
+                    node_request = Some((module, mouse_world, None));
                     state.selected_module = None;
                 }
 
                 if let (Some(from), Some(to)) =
                     (state.dragging_from.clone(), state.dragging_to.clone())
                 {
-                    let from_name = from.borrow().parent_id.clone();
-                    let from_id = from.borrow().label.clone();
-                    let to_name = to.borrow().parent_id.clone();
-                    let to_id = to.borrow().label.clone();
-                    let namefrom = format!("(({}):(\"{}\"))", from_name, from_id);
-                    let nameto = format!("(({}):(\"{}\"))", to_name, to_id);
-                    let name = namefrom.clone() + &nameto;
-
-                    let mut to_remove: Vec<String> = vec![];
-
-                    for (connection_name, _) in state.connections.iter() {
-                        if *connection_name == name || from_name == to_name {
-                            state.dragging_from = None;
-                            state.dragging_to = None;
-                            return;
-                        }
-                        if connection_name.ends_with(&nameto) {
-                            to_remove.push(connection_name.clone());
+                    let key = ConnectionKey::between(&from.borrow(), &to.borrow());
+
+                    if !types_compatible(&from.borrow().data_type, &to.borrow().data_type)
+                        || graph::creates_cycle(&state.connections, &key.from_node, &key.to_node)
+                        || state.connections.contains_key(&key)
+                    {
+                        state.dragging_from = None;
+                        state.dragging_to = None;
+                    } else {
+                        /* An input takes a single wire; the new one replaces it */
+                        let to_remove: Vec<ConnectionKey> = state
+                            .connections
+                            .keys()
+                            .filter(|k| k.to_node == key.to_node && k.to_port == key.to_port)
+                            .cloned()
+                            .collect();
+
+                        let mut commands: Vec<Command> = vec![];
+                        for remove_name in to_remove {
+                            Self::remove_connection(&remove_name, &mut state);
+                            commands.push(Command::RemoveConnection(remove_name));
                         }
-                    }
+                        commands.push(Command::AddConnection(key.clone()));
 
-                    for remove_name in to_remove {
-                        Self::remove_connection(&remove_name, &mut state);
+                        state.detached_wires.remove(&key);
+                        state.connections.insert(key, Connection::new(from, to));
+                        state.evaluation_requested = true;
+                        state.history.push(Command::Group(commands));
                     }
+                }
 
-                    state
-                        .connections
-                        .insert(name, Connection { from, to, z: 1 });
+                /* A wire let go over empty canvas opens the picker for a node to plug into it */
+                let dangling = match (&state.dragging_from, &state.dragging_to) {
+                    (Some(port), None) => Some((port.clone(), true)),
+                    (None, Some(port)) => Some((port.clone(), false)),
+                    _ => None,
+                };
+                if let Some((port, is_output)) = dangling
+                    && self.node_drag.is_none()
+                    && state.marquee.is_none()
+                {
+                    let port = port.borrow();
+                    let mouse_world: Vector2 =
+                        rl.get_screen_to_world2D(mouse.clone(), &*cam).into();
+                    let over_node = self.objects.values().any(|obj| {
+                        obj.borrow()
+                            .as_any()
+                            .downcast_ref::<Node>()
+                            .is_some_and(|node| {
+                                node.bounds().check_collision_point_rec(mouse_world.clone())
+                            })
+                    });
+
+                    if !over_node
+                        && (port.position.clone() - mouse_world.clone()).magnitude() > 12.0
+                    {
+                        wire_drop = Some((
+                            WireDrop {
+                                node: port.parent_id.clone(),
+                                port: port.label.clone(),
+                                is_output,
+                                data_type: port.data_type.clone(),
+                            },
+                            mouse_world,
+                        ));
+                    }
                 }
+
                 state.dragging_from = None;
                 state.dragging_to = None;
             }
 
-            let mut to_remove: Vec<String> = vec![];
+            let mouse_world: Vector2 = rl.get_screen_to_world2D(mouse.clone(), &*cam).into();
+            let over_ui = mouse.y < 40.0
+                || over_minimap
+                || self
+                    .node_palette
+                    .as_ref()
+                    .is_some_and(|palette| palette.open)
+                || Rectangle::new(0.0, 0.0, state.selector_size.x, state.selector_size.y)
+                    .check_collision_point_rec(mouse.clone());
+            let wire_input = !over_ui
+                && !menu_was_open
+                && !self.node_active
+                && state.dialog.is_none()
+                && state.dragging_from.is_none()
+                && state.dragging_to.is_none();
+
+            /* Reroute dots are grabbed before the wire under them */
+            if wire_input && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                self.reroute_drag = state.connections.iter().find_map(|(key, conn)| {
+                    Some(RerouteDrag {
+                        key: key.clone(),
+                        index: conn.reroute_at(&mouse_world)?,
+                        before: conn.reroutes.clone(),
+                    })
+                });
+                if self.reroute_drag.is_some() {
+                    self.dragging = false;
+                    self.last_wire_click = None;
+                }
+            }
+
+            if let Some(drag) = &self.reroute_drag {
+                let mut position = mouse_world.clone();
+                if settings.snap_to_grid
+                    && !rl.is_key_down(KeyboardKey::KEY_LEFT_ALT)
+                    && !rl.is_key_down(KeyboardKey::KEY_RIGHT_ALT)
+                {
+                    position = settings.snap_position(position);
+                }
+                if let Some(point) = state
+                    .connections
+                    .get_mut(&drag.key)
+                    .and_then(|conn| conn.reroutes.get_mut(drag.index))
+                {
+                    *point = position;
+                }
+
+                if !rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+                    let drag = self.reroute_drag.take().unwrap();
+                    if let Some(conn) = state.connections.get(&drag.key)
+                        && conn.reroutes != drag.before
+                    {
+                        let after = conn.reroutes.clone();
+                        state.history.push(Command::SetReroutes {
+                            key: drag.key,
+                            before: drag.before,
+                            after,
+                        });
+                    }
+                }
+            }
+
+            let mut reroute_edit: Option<(ConnectionKey, Vec<Vector2>)> = None;
+            let mut style_edit: Option<(ConnectionKey, Option<WireStyle>)> = None;
 
-            for (conn_name, conn) in &mut state.connections {
-                conn.update(rl, thread, &cam);
-                let from_pos = conn.from.borrow().position.clone();
-                let to_pos = conn.to.borrow().position.clone();
+            for (key, conn) in &state.connections {
+                if !wire_input || self.reroute_drag.is_some() {
+                    break;
+                }
+
+                let reroute = conn.reroute_at(&mouse_world);
+                let Some(segment) =
+                    Window::point_in_bezier_line(&conn.route, mouse_world.clone(), 15.0)
+                        .or(reroute)
+                else {
+                    continue;
+                };
+
+                if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+                    context_request = Some(ContextTarget::Wire {
+                        key: key.clone(),
+                        segment,
+                        reroute,
+                        at: mouse_world.clone(),
+                    });
+                    break;
+                }
+
+                if rl.is_key_pressed(KeyboardKey::KEY_W) && !editing_text {
+                    style_edit = Some((key.clone(), WireStyle::next_override(conn.style)));
+                    break;
+                }
 
-                if Window::point_in_bezier_line(
-                    from_pos.clone(),
-                    to_pos.clone(),
-                    rl.get_screen_to_world2D(mouse.clone(), &*cam).into(),
-                    15.0,
-                ) {
-                    if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
-                        to_remove.push(conn_name.clone());
+                if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                    let now = rl.get_time();
+                    match &self.last_wire_click {
+                        Some((last, time)) if last == key && now - time <= DOUBLE_CLICK_TIME => {
+                            let mut reroutes = conn.reroutes.clone();
+                            reroutes.insert(segment, mouse_world.clone());
+                            reroute_edit = Some((key.clone(), reroutes));
+                            self.last_wire_click = None;
+                        }
+                        _ => self.last_wire_click = Some((key.clone(), now)),
                     }
+                    break;
                 }
             }
 
-            for remove_name in to_remove {
-                Self::remove_connection(&remove_name, &mut state);
+            if let Some((key, after)) = reroute_edit {
+                Self::set_reroutes(key, after, &mut state);
+            }
+
+            if let Some((key, after)) = style_edit
+                && let Some(conn) = state.connections.get_mut(&key)
+            {
+                let before = std::mem::replace(&mut conn.style, after);
+                state
+                    .history
+                    .push(Command::SetWireStyle { key, before, after });
             }
         });
 
@@ -342,7 +856,45 @@ impl Window {
             .clamp(world_min.y + half_screen.y, world_max.y - half_screen.y);
 
         let mut active_index: Option<usize> = None;
+        let mut active_id: Option<String> = None;
         let mut to_remove: Vec<String> = vec![];
+        let mut grabbed_annotation: Option<(String, Vector2)> = None;
+        let mut annotation_busy = false;
+        let mut annotation_request: Option<AnnotationKind> = None;
+        let mut history_action: Option<&str> = None;
+        let mut clipboard_action: Option<&str> = None;
+        let mut arrange_action: Option<String> = None;
+        let mut tool_bar_events: Vec<String> = vec![];
+
+        /* Text fields, the palette's search included, keep their own shortcuts */
+        let typing = editing_text
+            || self
+                .node_palette
+                .as_ref()
+                .is_some_and(|palette| palette.open);
+        if !typing
+            && (rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+                || rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL))
+        {
+            let shift = rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
+                || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+
+            if rl.is_key_pressed(KeyboardKey::KEY_Z) {
+                history_action = Some(if shift { "redo" } else { "undo" });
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_C) {
+                clipboard_action = Some("copy");
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_X) {
+                clipboard_action = Some("cut");
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_V) {
+                clipboard_action = Some("paste");
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_D) {
+                clipboard_action = Some("duplicate");
+            }
+        }
 
         if let Some(selector) = &mut self.node_selector {
             selector.update(rl, thread, &cam);
@@ -350,26 +902,44 @@ impl Window {
                 tool_bar.position.x = selector.size.x;
                 tool_bar.update(rl, thread, &cam);
 
-                let events = std::mem::take(&mut tool_bar.events);
-
-                for ev in events {
-                    match ev.as_str() {
-                        "save_file" => {
-                            self.save_file(cam.clone());
-                        }
-                        "open_file" => self.open_file(),
-                        "new_file" => self.new_file(),
-                        _ => {}
-                    }
-                }
+                tool_bar_events = std::mem::take(&mut tool_bar.events);
             }
         }
 
+        let pointer_world: Vector2 = rl.get_screen_to_world2D(mouse.clone(), &*cam).into();
+        let pointer_over_node = self.objects.values().any(|obj| {
+            obj.borrow()
+                .as_any()
+                .downcast_ref::<Node>()
+                .is_some_and(|node| {
+                    node.bounds()
+                        .check_collision_point_rec(pointer_world.clone())
+                })
+        });
+
         for (i, (key, obj)) in self.objects.iter().enumerate() {
             let mut obj_mut = obj.borrow_mut();
 
+            let canvas_object =
+                obj_mut.as_any().is::<Node>() || obj_mut.as_any().is::<Annotation>();
+            if canvas_object && !canvas_input {
+                continue;
+            }
+            if let Some(annotation) = obj_mut.as_any_mut().downcast_mut::<Annotation>() {
+                annotation.under_node = pointer_over_node;
+            }
             obj_mut.update(rl, thread, &cam);
 
+            if let Some(annotation) = obj_mut.as_any().downcast_ref::<Annotation>() {
+                if let Some(grab) = &annotation.grabbed {
+                    grabbed_annotation = Some((key.clone(), grab.clone()));
+                }
+                if annotation.context_requested && !menu_was_open && context_request.is_none() {
+                    context_request = Some(ContextTarget::Annotation { id: key.clone() });
+                }
+                annotation_busy |= annotation.busy();
+            }
+
             if let Some(node) = obj_mut.as_any_mut().downcast_mut::<Node>() {
                 if let Ok(active) = node.get_property("active".to_string()).downcast::<bool>() {
                     if !*active {
@@ -383,105 +953,459 @@ impl Window {
                         .set_property("active".to_string(), Box::new(false));
                 }
 
-                if rl.is_key_pressed(KeyboardKey::KEY_DELETE)
-                    || rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
-                {
-                    to_remove.push(key.clone());
-                    EDITOR_STATE.with(|editor_state| {
-                        let mut state = editor_state.borrow_mut();
-                        let mut to_remove: Vec<String> = vec![];
-
-                        for (connection, _) in &mut state.connections {
-                            if connection.contains(node.id.as_str()) {
-                                to_remove.push(connection.clone());
-                            }
-                        }
-
-                        for remove_name in to_remove {
-                            Self::remove_connection(&remove_name, &mut state);
-                        }
-                    })
+                if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) && !menu_was_open {
+                    context_request = Some(ContextTarget::Node {
+                        id: key.clone(),
+                        at: rl.get_screen_to_world2D(mouse.clone(), &*cam).into(),
+                    });
                 }
 
                 active_index = Some(i);
+                active_id = Some(key.clone());
             }
-
-            self.node_active = active_index.is_some();
         }
+        self.node_active = active_index.is_some() || annotation_busy || self.node_drag.is_some();
 
-        for key in to_remove {
-            self.objects.remove(&key);
-        }
+        let mouse_world: Vector2 = rl.get_screen_to_world2D(mouse.clone(), &*cam).into();
 
-        drop(cam);
-        drop(settings);
+        let mut palette_open = false;
+        if let Some(palette) = &mut self.node_palette {
+            let was_open = palette.open;
+            palette.update(rl, thread, &cam);
+            if let Some(type_name) = palette.chosen.take() {
+                node_request = Some((
+                    type_name,
+                    palette.world_position.clone(),
+                    self.wire_drop.take(),
+                ));
+            }
+            if !palette.open {
+                self.wire_drop = None;
+            }
+
+            if let Some((drop, world_position)) = wire_drop {
+                let module_manager = self.module_manager.borrow();
+                palette.show(mouse.clone(), world_position, screen, |type_name| {
+                    module_manager.ports(type_name).is_some_and(|ports| {
+                        ports.iter().any(|(_, is_output, data_type)| {
+                            match (drop.is_output, *is_output) {
+                                (true, false) => types_compatible(&drop.data_type, data_type),
+                                (false, true) => types_compatible(data_type, &drop.data_type),
+                                _ => false,
+                            }
+                        })
+                    })
+                });
+                self.wire_drop = Some(drop);
+            } else if !was_open
+                && !editing_text
+                && (rl.is_key_pressed(KeyboardKey::KEY_SPACE)
+                    || rl.is_key_pressed(KeyboardKey::KEY_TAB))
+                && EDITOR_STATE.with_borrow(|state| state.dialog.is_none())
+            {
+                palette.show(mouse.clone(), mouse_world.clone(), screen, |_| true);
+            }
+            palette_open = was_open || palette.open;
+        }
+        let additive = rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
+            || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT)
+            || rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+            || rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
 
         EDITOR_STATE.with(|editor_state| {
             let mut state = editor_state.borrow_mut();
+            state.selection.retain(|id| self.objects.contains_key(id));
 
-            if let Some(dialog) = state.dialog.as_mut() {
-                let event = dialog.update(rl);
+            let over_ui = mouse.y < 40.0
+                || palette_open
+                || over_minimap
+                || self.reroute_drag.is_some()
+                || Rectangle::new(0.0, 0.0, state.selector_size.x, state.selector_size.y)
+                    .check_collision_point_rec(mouse.clone());
 
-                if event != "".to_string() {
-                    state.dialog = None;
+            if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
+                && state.dialog.is_none()
+                && !over_ui
+                && !menu_was_open
+                && context_request.is_none()
+                && active_id.is_none()
+            {
+                context_request = Some(ContextTarget::Canvas {
+                    at: mouse_world.clone(),
+                });
+            }
 
-                    match event.as_str() {
-                        "file.open" => {
-                            if let Some(save) = SaveFile::read() {
-                                self.load_from_save(save.clone(), &mut state);
-                            }
-                        }
-                        "file.open_save" => {
-                            if !self.save_file_with_state(&mut state, self.camera.borrow().clone())
-                            {
-                                return;
-                            }
-                            if let Some(save) = SaveFile::read() {
-                                self.load_from_save(save.clone(), &mut state);
+            if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+                && state.dialog.is_none()
+                && !over_ui
+                && !menu_was_open
+            {
+                match (&active_id, &grabbed_annotation) {
+                    (Some(id), _) => {
+                        if additive {
+                            if !state.selection.remove(id) {
+                                state.selection.insert(id.clone());
                             }
+                        } else if !state.selection.contains(id) {
+                            state.selection = BTreeSet::from([id.clone()]);
                         }
-                        "file.new" => {
-                            state.connections.clear();
-                            state.node_names.clear();
-                            state.project_name = "untitled".to_string();
-                            self.objects.retain(|name, _| name == "grid");
-                            let mut cam = self.camera.borrow_mut();
-                            cam.target = Vector2::zero();
-                            cam.zoom = 1.0;
+
+                        let mut group: Vec<String> = state.selection.iter().cloned().collect();
+                        if !group.contains(id) {
+                            group = vec![id.clone()];
                         }
-                        "file.new_save" => {
-                            if !self.save_file_with_state(&mut state, self.camera.borrow().clone())
-                            {
-                                return;
-                            }
-                            state.connections.clear();
-                            state.node_names.clear();
-                            state.project_name = "untitled".to_string();
-                            self.objects.retain(|name, _| name == "grid");
-                            let mut cam = self.camera.borrow_mut();
-                            cam.target = Vector2::zero();
-                            cam.zoom = 1.0;
+                        self.node_drag = Some(NodeDrag {
+                            leader: id.clone(),
+                            origins: group
+                                .into_iter()
+                                .filter_map(|id| {
+                                    let position = Self::node_position(&self.objects, &id)?;
+                                    Some((id, position))
+                                })
+                                .collect(),
+                            grab: None,
+                        });
+                    }
+                    (None, Some((id, grab))) => {
+                        self.node_drag = Some(NodeDrag {
+                            leader: id.clone(),
+                            origins: Self::annotation_group(&self.objects, id)
+                                .into_iter()
+                                .filter_map(|id| {
+                                    let position = Self::node_position(&self.objects, &id)?;
+                                    Some((id, position))
+                                })
+                                .collect(),
+                            grab: Some(grab.clone()),
+                        });
+                    }
+                    (None, None) if state.dragging_from.is_none() => {
+                        if additive {
+                            state.marquee = Some((mouse_world.clone(), mouse_world.clone()));
+                        } else {
+                            state.selection.clear();
                         }
-                        _ => {}
                     }
+                    _ => {}
                 }
             }
-        });
-    }
 
-    fn draw(&self, rl: &mut RaylibHandle, thread: &RaylibThread) {
-        let mouse = rl.get_screen_to_world2D(
-            rl.get_mouse_position(),
-            Camera2D::from(self.camera.borrow().clone()),
-        );
-        let mut d = rl.begin_drawing(thread);
-        d.clear_background(Color::WHITE);
+            if let Some(drag) = &self.node_drag
+                && rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
+                && let Some((_, leader_origin)) =
+                    drag.origins.iter().find(|(id, _)| *id == drag.leader)
+                && let Some(mut leader_position) = match &drag.grab {
+                    Some(grab) => Some(mouse_world.clone() - grab.clone()),
+                    None => Self::node_position(&self.objects, &drag.leader),
+                }
+            {
+                if settings.snap_to_grid
+                    && !rl.is_key_down(KeyboardKey::KEY_LEFT_ALT)
+                    && !rl.is_key_down(KeyboardKey::KEY_RIGHT_ALT)
+                {
+                    leader_position = settings.snap_position(leader_position);
+                }
 
-        {
-            let mut cam = d.begin_mode2D(self.camera.borrow().clone());
+                let delta = leader_position - leader_origin.clone();
+                for (id, origin) in &drag.origins {
+                    if let Some(obj) = self.objects.get(id) {
+                        obj.borrow_mut().set_property(
+                            "position".to_string(),
+                            Box::new(origin.clone() + delta.clone()),
+                        );
+                    }
+                }
+            }
 
-            let mut sorted_objs: Vec<Rc<RefCell<dyn Object>>> =
-                self.objects.values().cloned().collect();
+            if let Some(marquee) = &mut state.marquee {
+                marquee.1 = mouse_world.clone();
+            }
+
+            if rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+                if let Some(drag) = self.node_drag.take() {
+                    let moves: Vec<(String, Vector2, Vector2)> = drag
+                        .origins
+                        .into_iter()
+                        .filter_map(|(id, from)| {
+                            let to = Self::node_position(&self.objects, &id)?;
+                            (to != from).then_some((id, from, to))
+                        })
+                        .collect();
+
+                    if !moves.is_empty() {
+                        state.history.push(Command::MoveNodes(moves));
+                    }
+                }
+
+                if let Some((start, end)) = state.marquee.take() {
+                    let area = Rectangle::new(
+                        start.x.min(end.x),
+                        start.y.min(end.y),
+                        (start.x - end.x).abs(),
+                        (start.y - end.y).abs(),
+                    );
+
+                    for (id, obj) in &self.objects {
+                        if let Some(node) = obj.borrow().as_any().downcast_ref::<Node>()
+                            && node.bounds().check_collision_recs(&area)
+                        {
+                            state.selection.insert(id.clone());
+                        }
+                    }
+                }
+            }
+
+            if rl.is_key_pressed(KeyboardKey::KEY_DELETE) && !editing_text && !palette_open {
+                for id in &state.selection {
+                    if !to_remove.contains(id) {
+                        to_remove.push(id.clone());
+                    }
+                }
+            }
+
+            for (id, obj) in &self.objects {
+                if let Some(node) = obj.borrow_mut().as_any_mut().downcast_mut::<Node>() {
+                    node.selected = state.selection.contains(id);
+                }
+            }
+        });
+
+        drop(cam);
+        drop(settings);
+
+        for ev in tool_bar_events {
+            match ev.as_str() {
+                "save_file" => {
+                    self.save_file(false);
+                }
+                "save_file_as" => {
+                    self.save_file(true);
+                }
+                "open_file" => self.open_file(None),
+                "new_file" => self.new_file(),
+                "exit" => self.exit(),
+                "undo" => history_action = Some("undo"),
+                "redo" => history_action = Some("redo"),
+                "copy" => clipboard_action = Some("copy"),
+                "cut" => clipboard_action = Some("cut"),
+                "paste" => clipboard_action = Some("paste"),
+                "duplicate" => clipboard_action = Some("duplicate"),
+                "toggle_snap" => {
+                    let mut settings = self.settings.borrow_mut();
+                    settings.snap_to_grid = !settings.snap_to_grid;
+                    if let Err(e) = settings.save_user_settings() {
+                        eprintln!("Snapping could not be saved: {}", e);
+                    }
+                }
+                "align_left"
+                | "align_right"
+                | "align_top"
+                | "align_bottom"
+                | "distribute_horizontal"
+                | "distribute_vertical"
+                | "auto_arrange" => arrange_action = Some(ev.clone()),
+                "add_note" => annotation_request = Some(AnnotationKind::Note),
+                "add_frame" => annotation_request = Some(AnnotationKind::Frame),
+                _ => {
+                    if let Some(path) = ev.strip_prefix("open_recent:") {
+                        self.open_file(Some(path.to_string()));
+                    } else if let Some(style) = ev
+                        .strip_prefix("wire_style:")
+                        .and_then(WireStyle::from_name)
+                    {
+                        let mut settings = self.settings.borrow_mut();
+                        settings.wire_style = style;
+                        if let Err(e) = settings.save_user_settings() {
+                            eprintln!("Wire style could not be saved: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+        self.refresh_recent_menu();
+
+        EDITOR_STATE.with(|editor_state| {
+            let mut state = editor_state.borrow_mut();
+
+            if !to_remove.is_empty() {
+                self.delete_nodes(&to_remove, &mut state);
+            }
+            if let Some(kind) = annotation_request {
+                self.add_annotation(kind, &mut state);
+            }
+
+            match history_action {
+                Some("undo") => self.undo(&mut state),
+                Some("redo") => self.redo(&mut state),
+                _ => {}
+            }
+
+            if let Some((type_name, center, wire)) = node_request {
+                self.create_node(&type_name, center, wire, &mut state);
+            }
+
+            match arrange_action.as_deref() {
+                Some("auto_arrange") => self.auto_arrange(&mut state),
+                Some("distribute_horizontal") => self.distribute_selection(true, &mut state),
+                Some("distribute_vertical") => self.distribute_selection(false, &mut state),
+                Some(action) => {
+                    if let Some(edge) = action.strip_prefix("align_") {
+                        self.align_selection(edge, &mut state);
+                    }
+                }
+                None => {}
+            }
+
+            match clipboard_action {
+                Some("copy") => self.copy_selection(rl, &state),
+                Some("cut") if !state.selection.is_empty() => {
+                    self.copy_selection(rl, &state);
+                    let ids: Vec<String> = state.selection.iter().cloned().collect();
+                    self.delete_nodes(&ids, &mut state);
+                }
+                Some("paste") => {
+                    if let Ok(text) = rl.get_clipboard_text()
+                        && let Ok(fragment) = SaveFile::parse(&text)
+                    {
+                        self.paste_fragment(fragment, mouse_world.clone(), &mut state);
+                    }
+                }
+                Some("duplicate") => {
+                    if let Some(fragment) = self.selection_fragment(&state) {
+                        self.paste_fragment(fragment, mouse_world.clone(), &mut state);
+                    }
+                }
+                _ => {}
+            }
+
+            if let Some(event) = context_events.pop()
+                && let Some(target) = self.context_target.take()
+            {
+                self.run_context_action(rl, &event, target, &mut state);
+            }
+            if let Some(target) = context_request {
+                self.open_context_menu(target, mouse.clone(), screen_size.clone(), &mut state);
+            }
+
+            if rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+                state.history.seal();
+            }
+
+            if state.history.take_changed() {
+                state.dirty = true;
+                state.autosave_pending = true;
+            }
+
+            let interval = self.settings.borrow().autosave_interval as f64;
+            if state.autosave_pending
+                && interval > 0.0
+                && rl.get_time() - state.last_autosave >= interval
+            {
+                self.autosave(&mut state);
+                state.last_autosave = rl.get_time();
+            }
+
+            if state.evaluation_requested {
+                state.evaluation_requested = false;
+                graph::evaluate(&self.objects, &state.connections);
+            }
+
+            let obstacles = self.node_bounds();
+            let wire_style = self.settings.borrow().wire_style;
+            for conn in state.connections.values_mut() {
+                conn.update_route(wire_style, &obstacles);
+            }
+
+            if let Some(minimap) = &mut self.minimap {
+                minimap.nodes = obstacles;
+                minimap.wires = state
+                    .connections
+                    .values()
+                    .flat_map(|conn| conn.route.iter().flat_map(|leg| leg.windows(2)))
+                    .map(|segment| (segment[0].clone(), segment[1].clone()))
+                    .collect();
+            }
+        });
+
+        EDITOR_STATE.with(|editor_state| {
+            let mut state = editor_state.borrow_mut();
+
+            if let Some(dialog) = state.dialog.as_mut() {
+                let event = dialog.update(rl);
+
+                if event != "".to_string() {
+                    state.dialog = None;
+
+                    match event.as_str() {
+                        "file.open" => {
+                            let path = state.pending_open.take();
+                            self.open_project(&mut state, path);
+                        }
+                        "file.open_save" => {
+                            if !self.save_file_with_state(&mut state, false) {
+                                return;
+                            }
+                            let path = state.pending_open.take();
+                            self.open_project(&mut state, path);
+                        }
+                        "file.new" => self.clear_project(&mut state),
+                        "file.new_save" => {
+                            if !self.save_file_with_state(&mut state, false) {
+                                return;
+                            }
+                            self.clear_project(&mut state);
+                        }
+                        "file.exit" => {
+                            /* Leaving without saving discards the changes, recovery file included */
+                            state.dirty = false;
+                            state.exit_requested = true;
+                        }
+                        "file.exit_save" => {
+                            if self.save_file_with_state(&mut state, false) {
+                                state.exit_requested = true;
+                            }
+                        }
+                        "file.recover" => {
+                            if let Some(path) = state.pending_recovery.take() {
+                                match SaveFile::from_file(&path) {
+                                    Ok(save) => {
+                                        self.load_from_save(save, &mut state);
+                                        state.dirty = true;
+                                        /* The work continues as untitled, so it moves to that file */
+                                        Self::remove_recovery(&path);
+                                        self.autosave(&mut state);
+                                    }
+                                    Err(e) => eprintln!("{}", e),
+                                }
+                            }
+                        }
+                        "file.discard_recovery" => {
+                            if let Some(path) = state.pending_recovery.take() {
+                                Self::remove_recovery(&path);
+                            }
+                        }
+                        _ => {
+                            state.pending_open = None;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    fn draw(&self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        let mouse = rl.get_screen_to_world2D(
+            rl.get_mouse_position(),
+            Camera2D::from(self.camera.borrow().clone()),
+        );
+        let mut d = rl.begin_drawing(thread);
+        d.clear_background(Color::WHITE);
+
+        {
+            let mut cam = d.begin_mode2D(self.camera.borrow().clone());
+
+            let mut sorted_objs: Vec<Rc<RefCell<dyn Object>>> =
+                self.objects.values().cloned().collect();
 
             sorted_objs.sort_by(|a, b| {
                 let az = a
@@ -508,26 +1432,81 @@ impl Window {
                 for (_, connection) in &state.connections {
                     connection.draw(&mut cam, &self.camera.borrow());
                 }
+                if let Some((start, end)) = &state.marquee {
+                    let area = Rectangle::new(
+                        start.x.min(end.x),
+                        start.y.min(end.y),
+                        (start.x - end.x).abs(),
+                        (start.y - end.y).abs(),
+                    );
+                    let schemes = self.color_schemes.borrow();
+                    let scheme = self.settings.borrow().scheme.clone();
+
+                    cam.draw_rectangle_rec(
+                        area,
+                        schemes
+                            .get_color(&scheme, "selection_fill")
+                            .unwrap_or(Color::MAGENTA),
+                    );
+                    cam.draw_rectangle_lines_ex(
+                        area,
+                        1.0 / self.camera.borrow().zoom,
+                        schemes
+                            .get_color(&scheme, "selection_border")
+                            .unwrap_or(Color::MAGENTA),
+                    );
+                }
+                for obj in self.objects.values() {
+                    if let Some(node) = obj.borrow().as_any().downcast_ref::<Node>() {
+                        node.draw_error_tooltip(&mut cam, &self.camera.borrow());
+                    }
+                }
+                if state.dragging_from.is_none()
+                    && self.node_drag.is_none()
+                    && state.marquee.is_none()
+                    && let Some(drag_port) = &state.dragging_to
+                {
+                    self.draw_pending_wire(
+                        &mut cam,
+                        &[mouse.clone().into(), drag_port.borrow().position.clone()],
+                        (false, true),
+                        "connection_pending",
+                    );
+                }
                 if let Some(drag_port) = &state.dragging_from {
-                    cam.draw_line_bezier(
-                        drag_port.borrow().position.clone(),
-                        mouse.clone(),
-                        3.0,
-                        self.color_schemes
-                            .borrow()
-                            .get_color(&self.settings.borrow().scheme, "connection_pending")
-                            .unwrap(),
+                    let compatible = state.dragging_to.as_ref().is_none_or(|to| {
+                        types_compatible(&drag_port.borrow().data_type, &to.borrow().data_type)
+                    });
+
+                    self.draw_pending_wire(
+                        &mut cam,
+                        &[drag_port.borrow().position.clone(), mouse.clone().into()],
+                        (true, false),
+                        if compatible {
+                            "connection_pending"
+                        } else {
+                            "connection_invalid"
+                        },
                     );
                 }
             });
         }
 
+        if let Some(minimap) = &self.minimap {
+            minimap.draw(&mut d, &self.camera.borrow());
+        }
         if let Some(selector) = &self.node_selector {
             selector.draw(&mut d, &self.camera.borrow());
         }
         if let Some(tool_bar) = &self.tool_bar {
             tool_bar.draw(&mut d, &self.camera.borrow());
         }
+        if let Some(palette) = &self.node_palette {
+            palette.draw(&mut d, &self.camera.borrow());
+        }
+        if let Some(menu) = &self.context_menu {
+            menu.draw(&mut d, &self.camera.borrow());
+        }
 
         EDITOR_STATE.with(|state: &RefCell<EditorState>| {
             let mut state = state.borrow_mut();
@@ -538,6 +1517,27 @@ impl Window {
         });
     }
 
+    /// Draws a wire still being dragged, routed the way it will be once connected.
+    fn draw_pending_wire(
+        &self,
+        d: &mut impl RaylibDraw,
+        path: &[Vector2],
+        port_ends: (bool, bool),
+        color_name: &str,
+    ) {
+        let settings = self.settings.borrow();
+        let color = self
+            .color_schemes
+            .borrow()
+            .get_color(&settings.scheme, color_name)
+            .unwrap();
+
+        for leg in routing::route(settings.wire_style, path, port_ends, &self.node_bounds()) {
+            let points: Vec<raylib::prelude::Vector2> = leg.iter().map(Into::into).collect();
+            d.draw_spline_linear(&points, 3.0, color);
+        }
+    }
+
     fn load_translations(&mut self) {
         if let Ok(translations) = fs::read_dir("resources/translations") {
             for translation in translations.flatten() {
@@ -592,53 +1592,54 @@ impl Window {
         }
     }
 
-    fn point_in_bezier_line(p1: Vector2, p2: Vector2, point: Vector2, line_thickness: f32) -> bool {
-        fn dist(a: &Vector2, b: &Vector2) -> f32 {
-            let dx = a.x - b.x;
-            let dy = a.y - b.y;
-            (dx * dx + dy * dy).sqrt()
-        }
-
-        fn point_segment_distance(a: &Vector2, b: &Vector2, p: &Vector2) -> f32 {
-            let ab = b.clone() - a.clone();
-            let ap = p.clone() - a.clone();
-            let ab_len2 = ab.x * ab.x + ab.y * ab.y;
-            if ab_len2 == 0.0 {
-                return dist(a, p);
-            }
-            let t = ((ap.x * ab.x + ap.y * ab.y) / ab_len2).clamp(0.0, 1.0);
-            let proj = Vector2::new(a.x + ab.x * t, a.y + ab.y * t, None);
-            dist(&proj, p)
-        }
-
-        if (p1.x - p2.x).abs() < std::f32::EPSILON && (p1.y - p2.y).abs() < std::f32::EPSILON {
-            return dist(&p1, &point) <= line_thickness;
-        }
+    fn node_bounds(&self) -> Vec<Rectangle> {
+        self.objects
+            .values()
+            .filter_map(|obj| {
+                obj.borrow()
+                    .as_any()
+                    .downcast_ref::<Node>()
+                    .map(Node::bounds)
+            })
+            .collect()
+    }
 
-        let delta = p2.clone() - p1.clone();
-        let length = (delta.x * delta.x + delta.y * delta.y)
-            .sqrt()
-            .max(std::f32::EPSILON);
-        let perp = Vector2::new(-delta.y / length, delta.x / length, None);
-        let midpoint = Vector2::new((p1.x + p2.x) * 0.5, (p1.y + p2.y) * 0.5, None);
-        let control = midpoint + perp * (length * 0.25);
+    /// Index of the leg of `route` that passes within `line_thickness` of `point`.
+    fn point_in_bezier_line(
+        route: &[Vec<Vector2>],
+        point: Vector2,
+        line_thickness: f32,
+    ) -> Option<usize> {
+        route
+            .iter()
+            .position(|leg| routing::distance_to_polyline(leg, &point) <= line_thickness)
+    }
 
-        let segments = 64usize;
-        let mut prev = p1.clone();
-        for i in 1..=segments {
-            let t = (i as f32) / (segments as f32);
-            let omt = 1.0 - t;
-            let bx = omt * omt * p1.x + 2.0 * omt * t * control.x + t * t * p2.x;
-            let by = omt * omt * p1.y + 2.0 * omt * t * control.y + t * t * p2.y;
-            let cur = Vector2::new(bx, by, None);
+    /// Reserves the lowest free index for `type_name` and returns the resulting node id.
+    fn allocate_node_id(state: &mut EditorState, type_name: &str) -> String {
+        let index = Self::next_node_index(state, type_name);
+        Self::reserve_node_index(state, type_name, index)
+    }
 
-            if point_segment_distance(&prev, &cur, &point) <= line_thickness {
-                return true;
-            }
-            prev = cur;
+    /// Lowest free index for `type_name`, left free until it is reserved.
+    fn next_node_index(state: &EditorState, type_name: &str) -> usize {
+        match state.node_names.get(type_name) {
+            Some(list) if !list.is_empty() => Window::missing_numbers(list)
+                .first()
+                .cloned()
+                .unwrap_or_else(|| list.iter().max().cloned().unwrap() + 1),
+            _ => 1,
         }
+    }
 
-        false
+    fn reserve_node_index(state: &mut EditorState, type_name: &str, index: usize) -> String {
+        state
+            .node_names
+            .entry(type_name.to_string())
+            .or_insert_with(Vec::new)
+            .push(index);
+
+        format!("{}{}", type_name, index)
     }
 
     fn missing_numbers(vec: &Vec<usize>) -> Vec<usize> {
@@ -708,71 +1709,190 @@ impl Window {
         grid
     }
 
-    fn remove_connection(name: &str, state: &mut EditorState) {
-        if let Some(conn) = state.connections.remove(name) {
+    fn node_position(
+        objects: &HashMap<String, Rc<RefCell<dyn Object>>>,
+        id: &str,
+    ) -> Option<Vector2> {
+        objects
+            .get(id)?
+            .borrow()
+            .get_property("position".to_string())
+            .downcast::<Vector2>()
+            .ok()
+            .map(|position| *position)
+    }
+
+    /// Reroute points and style of a connection, `None` when it has neither.
+    fn wire_save(key: &ConnectionKey, conn: &Connection) -> Option<WireSave> {
+        (!conn.reroutes.is_empty() || conn.style.is_some()).then(|| WireSave {
+            connection: key.clone(),
+            points: conn.reroutes.iter().cloned().map(Into::into).collect(),
+            style: conn.style,
+        })
+    }
+
+    fn wire_saves(state: &EditorState, connections: &[ConnectionKey]) -> Vec<WireSave> {
+        connections
+            .iter()
+            .filter_map(|key| Self::wire_save(key, state.connections.get(key)?))
+            .collect()
+    }
+
+    fn apply_wire(conn: &mut Connection, wire: &WireSave, offset: Vector2) {
+        conn.reroutes = wire
+            .points
+            .iter()
+            .map(|point| Vector2::from(*point) + offset.clone())
+            .collect();
+        conn.style = wire.style;
+    }
+
+    fn restore_wires(wires: &[WireSave], offset: Vector2, state: &mut EditorState) {
+        for wire in wires {
+            if let Some(conn) = state.connections.get_mut(&wire.connection) {
+                Self::apply_wire(conn, wire, offset.clone());
+            }
+        }
+    }
+
+    fn set_reroutes(key: ConnectionKey, after: Vec<Vector2>, state: &mut EditorState) {
+        if let Some(conn) = state.connections.get_mut(&key) {
+            let before = std::mem::replace(&mut conn.reroutes, after.clone());
+            state
+                .history
+                .push(Command::SetReroutes { key, before, after });
+        }
+    }
+
+    fn remove_connection(key: &ConnectionKey, state: &mut EditorState) {
+        if let Some(conn) = state.connections.remove(key) {
+            if let Some(wire) = Self::wire_save(key, &conn) {
+                state.detached_wires.insert(key.clone(), wire);
+            }
             conn.to.borrow_mut().write(Python::attach(|py| py.None()));
+            state.evaluation_requested = true;
         }
     }
 
-    fn save_file(&self, cam: Camera) -> bool {
-        EDITOR_STATE.with(|state| self.save_file_with_state(&mut state.borrow_mut(), cam))
+    fn save_file(&self, save_as: bool) -> bool {
+        EDITOR_STATE.with(|state| self.save_file_with_state(&mut state.borrow_mut(), save_as))
+    }
+
+    /// Writes to the project's own file, asking for a path only the first time or for Save As.
+    fn save_file_with_state(&self, state: &mut EditorState, save_as: bool) -> bool {
+        let path = match (&state.project_path, save_as) {
+            (Some(path), false) => path.clone(),
+            _ => match SaveFile::save_dialog(&state.project_name) {
+                Some(path) => path,
+                None => return false,
+            },
+        };
+
+        if let Some(name) = Path::new(&path).file_stem() {
+            state.project_name = name.to_string_lossy().to_string();
+        }
+
+        let save = self.current_save(state, &self.camera.borrow());
+        if let Err(e) = save.write_to(&path) {
+            eprintln!("{}", e);
+            return false;
+        }
+
+        Self::discard_recovery(state.project_path.as_deref());
+        state.save_file = Some(save);
+        state.project_path = Some(path.clone());
+        state.dirty = false;
+        state.autosave_pending = false;
+        self.add_recent_file(&path);
+
+        true
     }
 
-    fn save_file_with_state(&self, state: &mut EditorState, cam: Camera) -> bool {
+    fn current_save(&self, state: &EditorState, cam: &Camera) -> SaveFile {
         let nodes: Vec<NodeSave> = self
             .objects
-            .iter()
-            .filter_map(|(_, obj)| {
-                let obj = obj.borrow();
-                let id = *obj
-                    .get_property("id".to_string())
-                    .downcast::<String>()
-                    .ok()?;
-                let type_name = *obj
-                    .get_property("type_name".to_string())
-                    .downcast::<String>()
-                    .ok()?;
-                let position = *obj
-                    .get_property("position".to_string())
-                    .downcast::<crate::structs::Vector2>()
-                    .ok()?;
-                Some(NodeSave {
-                    id,
-                    type_name,
-                    position: position.into(),
-                })
+            .values()
+            .filter_map(|obj| {
+                obj.borrow()
+                    .as_any()
+                    .downcast_ref::<Node>()
+                    .map(NodeSave::from_node)
             })
             .collect();
+        let mut connections: Vec<ConnectionKey> = state.connections.keys().cloned().collect();
+        connections.sort();
 
-        state.save_file = Some(SaveFile::from(
+        let mut save = SaveFile::from(
             state.project_name.clone(),
             nodes,
-            state.connections.keys().cloned().collect(),
+            connections,
             CameraSave {
                 position: cam.target.clone().into(),
                 zoom: cam.zoom,
             },
-        ));
+        );
+        save.annotations = self
+            .objects
+            .values()
+            .filter_map(|obj| {
+                obj.borrow()
+                    .as_any()
+                    .downcast_ref::<Annotation>()
+                    .map(AnnotationSave::from_annotation)
+            })
+            .collect();
+        save.annotations.sort_by(|a, b| a.id.cmp(&b.id));
+        save.wires = Self::wire_saves(state, &save.connections);
+        save
+    }
 
-        drop(cam);
+    fn autosave(&self, state: &mut EditorState) {
+        state.autosave_pending = false;
 
-        if let Some(save) = &state.save_file {
-            save.write()
-        } else {
-            false
+        let Some(path) = recovery_path(state.project_path.as_deref()) else {
+            eprintln!("Autosave failed: no per-user data directory");
+            return;
+        };
+        if let Some(dir) = Path::new(&path).parent()
+            && let Err(e) = fs::create_dir_all(dir)
+        {
+            eprintln!("Autosave failed: {}: {}", dir.display(), e);
+            return;
+        }
+
+        let save = self.current_save(state, &self.camera.borrow());
+        if let Err(e) = save.write_to(&path) {
+            eprintln!("Autosave failed: {}", e);
+        }
+    }
+
+    /// Drops the unsaved work kept for the project at `project_path`, or for untitled work.
+    fn discard_recovery(project_path: Option<&str>) {
+        if let Some(path) = recovery_path(project_path) {
+            Self::remove_recovery(&path);
+        }
+    }
+
+    fn remove_recovery(path: &str) {
+        if Path::new(path).exists()
+            && let Err(e) = fs::remove_file(path)
+        {
+            eprintln!("{}: {}", path, e);
         }
     }
 
-    fn open_file(&self) {
+    /// `path` skips the file picker, as for entries of the recent files menu.
+    fn open_file(&self, path: Option<String>) {
         EDITOR_STATE.with(|state| {
             let mut state = state.borrow_mut();
+            state.pending_open = path;
 
             state.dialog = Some(Dialog::new(
                 "file.open".to_string(),
                 vec![
                     (DialogButton::Yes, "file.open_save".to_string()),
                     (DialogButton::No, "file.open".to_string()),
-                    (DialogButton::Cancel, "".to_string()),
+                    (DialogButton::Cancel, "dialog.close".to_string()),
                 ],
                 self.color_schemes.clone(),
                 self.settings.clone(),
@@ -784,108 +1904,1219 @@ impl Window {
 
     pub fn load_from_save(&mut self, save: SaveFile, state: &mut EditorState) {
         self.objects.retain(|key, _| key == "grid");
+        state.project_name = save.project_name.clone();
+        state.dirty = false;
+        state.autosave_pending = false;
+        state.connections.clear();
+        state.node_names.clear();
+        state.history.clear();
+        state.detached_wires.clear();
 
-        let lib = self.module_manager.clone();
-        let active_font = self.active_font.clone().unwrap();
-        let translations = self.translations.clone();
-        let color_schemes = self.color_schemes.clone();
-        let settings = self.settings.clone();
-
-        for n in save.nodes.into_iter() {
-            if let Some(node) = lib.borrow().generate(
-                Vector2::zero(),
-                n.type_name.clone(),
-                active_font.clone(),
-                translations.clone(),
-                color_schemes.clone(),
-                settings.clone(),
-                n.id.clone(),
-            ) {
-                node.borrow_mut().position = n.position.into();
-
-                self.objects
-                    .insert(n.id.clone(), node.clone() as Rc<RefCell<dyn Object>>);
-
-                let type_name = n.type_name.clone();
-                let list = state
-                    .node_names
-                    .entry(type_name.clone())
-                    .or_insert_with(Vec::new);
-                let index_str = n.id.trim_start_matches(&type_name.clone());
-                if let Ok(index) = index_str.parse::<usize>() {
-                    list.push(index);
-                }
-            }
+        for n in &save.nodes {
+            self.spawn_node(n, &save.connections, state);
         }
 
-        state.connections.clear();
+        for key in &save.connections {
+            self.connect(key, state);
+        }
+        Self::restore_wires(&save.wires, Vector2::zero(), state);
 
-        for conn_name in save.connections.clone() {
-            println!("{}", conn_name.clone());
-            if let Some(((from_node, from_port), (to_node, to_port))) =
-                Self::parse_connection(&conn_name)
-            {
-                if let (Some(from), Some(to)) = (
-                    self.find_port(&from_node, &from_port),
-                    self.find_port(&to_node, &to_port),
-                ) {
-                    state
-                        .connections
-                        .insert(conn_name, Connection { from, to, z: 1 });
-                }
-            }
+        for annotation in &save.annotations {
+            self.spawn_annotation(annotation, state);
         }
 
+        state.evaluation_requested = true;
+
         let mut cam = self.camera.borrow_mut();
 
         cam.target = save.camera.position.into();
         cam.zoom = save.camera.zoom;
     }
 
-    pub fn find_port(
-        &self,
-        node_id: &str,
-        port_label: &str,
-    ) -> Option<Rc<RefCell<Box<crate::node::Port>>>> {
-        for (id, obj) in &self.objects {
-            if id.clone() == node_id.to_string() {
-                let node_ref = obj.borrow();
-                if let Some(node) = node_ref.as_any().downcast_ref::<Node>() {
-                    for (label, _, _, port) in &node.ports {
-                        if label == port_label {
-                            return Some(port.clone());
-                        }
-                    }
-                } else {
+    /// Nodes whose module isn't loaded become placeholders; `connections` gives them their ports.
+    fn spawn_node(
+        &mut self,
+        save: &NodeSave,
+        connections: &[ConnectionKey],
+        state: &mut EditorState,
+    ) -> Rc<RefCell<Node>> {
+        let generated = self.module_manager.borrow().generate(
+            Vector2::zero(),
+            save.type_name.clone(),
+            self.active_font.clone(),
+            self.translations.clone(),
+            self.color_schemes.clone(),
+            self.settings.clone(),
+            save.id.clone(),
+        );
+
+        let node = match generated {
+            Some(node) => {
+                {
+                    let mut node = node.borrow_mut();
+                    node.position = save.position.into();
+                    node.apply_component_values(&save.components);
+                    node.restore_state(&save.state);
+                    node.bypassed = save.bypassed;
                 }
+                node
             }
+            None => {
+                eprintln!(
+                    "Node type '{}' is not available, keeping {} as a placeholder",
+                    save.type_name, save.id
+                );
+                Node::placeholder(
+                    save,
+                    connections,
+                    self.active_font.clone(),
+                    self.translations.clone(),
+                    self.color_schemes.clone(),
+                    self.settings.clone(),
+                )
+            }
+        };
+
+        {
+            let mut node = node.borrow_mut();
+            node.collapsed = save.collapsed;
+            node.title = save.title.clone();
+            node.color = save.color.clone();
         }
-        None
-    }
 
-    pub fn parse_connection(s: &str) -> Option<((String, String), (String, String))> {
-        let mut parts = s.split("))((");
+        self.objects
+            .insert(save.id.clone(), node.clone() as Rc<RefCell<dyn Object>>);
 
-        let left = parts.next()?;
-        let right = parts.next()?;
+        let list = state
+            .node_names
+            .entry(save.type_name.clone())
+            .or_insert_with(Vec::new);
+        if let Ok(index) = save.id.trim_start_matches(&save.type_name).parse::<usize>()
+            && !list.contains(&index)
+        {
+            list.push(index);
+        }
 
-        let left = left.trim_start_matches("((");
-        let right = right.trim_end_matches("))");
+        node
+    }
 
-        fn parse_side(side: &str) -> Option<(String, String)> {
-            let parts: Vec<&str> = side.splitn(2, "):(").collect();
-            if parts.len() != 2 {
-                return None;
+    fn connect(&self, key: &ConnectionKey, state: &mut EditorState) -> bool {
+        if let (Some(from), Some(to)) = (
+            self.find_port(&key.from_node, &key.from_port),
+            self.find_port(&key.to_node, &key.to_port),
+        ) {
+            let mut connection = Connection::new(from, to);
+            if let Some(wire) = state.detached_wires.remove(key) {
+                Self::apply_wire(&mut connection, &wire, Vector2::zero());
             }
-            let node = parts[0].to_string();
-            let port = parts[1].trim_matches('"').to_string();
-            Some((node, port))
+            state.connections.insert(key.clone(), connection);
+            state.evaluation_requested = true;
+            true
+        } else {
+            false
         }
+    }
 
-        let left_tuple = parse_side(left)?;
-        let right_tuple = parse_side(right)?;
+    fn node_connections(state: &EditorState, ids: &[String]) -> Vec<ConnectionKey> {
+        state
+            .connections
+            .keys()
+            .filter(|key| ids.iter().any(|id| key.touches(id)))
+            .cloned()
+            .collect()
+    }
+
+    fn remove_nodes(&mut self, ids: &[String], state: &mut EditorState) {
+        for key in Self::node_connections(state, ids) {
+            Self::remove_connection(&key, state);
+        }
+        for id in ids {
+            self.objects.remove(id);
+        }
+        state.evaluation_requested = true;
+    }
 
-        Some((left_tuple, right_tuple))
+    /// Generates a `type_name` node centered on `center`, plugs `wire` into its first compatible
+    /// port and records both in the history.
+    fn create_node(
+        &mut self,
+        type_name: &str,
+        center: Vector2,
+        wire: Option<WireDrop>,
+        state: &mut EditorState,
+    ) -> Option<String> {
+        let index = Self::next_node_index(state, type_name);
+        let node = self.module_manager.borrow().generate(
+            Vector2::zero(),
+            type_name.to_string(),
+            self.active_font.clone(),
+            self.translations.clone(),
+            self.color_schemes.clone(),
+            self.settings.clone(),
+            format!("{}{}", type_name, index),
+        )?;
+        /* Taken only now, so a failed generate does not use up an id */
+        let id = Self::reserve_node_index(state, type_name, index);
+
+        let size = node.borrow().size.clone();
+        node.borrow_mut().position = center - size / 2.0;
+        self.objects.insert(id.clone(), node.clone());
+
+        state.evaluation_requested = true;
+        let mut commands = vec![Command::AddNodes {
+            nodes: vec![NodeSave::from_node(&node.borrow())],
+            connections: vec![],
+        }];
+
+        let key = wire.and_then(|wire| {
+            let node = node.borrow();
+            let (label, _, _, _) = node.ports.iter().find(|(_, is_output, _, port)| {
+                let port = port.borrow();
+                match (wire.is_output, *is_output) {
+                    (true, false) => types_compatible(&wire.data_type, &port.data_type),
+                    (false, true) => types_compatible(&port.data_type, &wire.data_type),
+                    _ => false,
+                }
+            })?;
+
+            Some(if wire.is_output {
+                ConnectionKey {
+                    from_node: wire.node,
+                    from_port: wire.port,
+                    to_node: id.clone(),
+                    to_port: label.clone(),
+                }
+            } else {
+                ConnectionKey {
+                    from_node: id.clone(),
+                    from_port: label.clone(),
+                    to_node: wire.node,
+                    to_port: wire.port,
+                }
+            })
+        });
+
+        if let Some(key) = key {
+            /* An input takes a single wire; the new one replaces it */
+            let replaced: Vec<ConnectionKey> = state
+                .connections
+                .keys()
+                .filter(|k| k.to_node == key.to_node && k.to_port == key.to_port)
+                .cloned()
+                .collect();
+            for old in replaced {
+                Self::remove_connection(&old, state);
+                commands.push(Command::RemoveConnection(old));
+            }
+            state.detached_wires.remove(&key);
+            if self.connect(&key, state) {
+                commands.push(Command::AddConnection(key));
+            }
+        }
+
+        state.history.push(if commands.len() == 1 {
+            commands.pop().unwrap()
+        } else {
+            Command::Group(commands)
+        });
+        Some(id)
+    }
+
+    fn spawn_annotation(&mut self, save: &AnnotationSave, state: &mut EditorState) {
+        let annotation = Annotation::new(
+            save,
+            self.active_font.clone().unwrap(),
+            self.color_schemes.clone(),
+            self.settings.clone(),
+        );
+        self.objects
+            .insert(save.id.clone(), Rc::new(RefCell::new(annotation)));
+
+        let name = save.kind.name();
+        let list = state
+            .node_names
+            .entry(name.to_string())
+            .or_insert_with(Vec::new);
+        if let Ok(index) = save.id.trim_start_matches(name).parse::<usize>()
+            && !list.contains(&index)
+        {
+            list.push(index);
+        }
+    }
+
+    /// Adds a note at the middle of the view, or a frame around the selection when there is one.
+    fn add_annotation(&mut self, kind: AnnotationKind, state: &mut EditorState) {
+        let center = self.camera.borrow().target.clone();
+        let selected = self.selected_bounds(state);
+
+        let (position, size) = if kind == AnnotationKind::Frame && !selected.is_empty() {
+            let left = selected
+                .iter()
+                .map(|(_, r, _)| r.x)
+                .fold(f32::INFINITY, f32::min);
+            let top = selected
+                .iter()
+                .map(|(_, r, _)| r.y)
+                .fold(f32::INFINITY, f32::min);
+            let right = selected
+                .iter()
+                .map(|(_, r, _)| r.x + r.width)
+                .fold(f32::NEG_INFINITY, f32::max);
+            let bottom = selected
+                .iter()
+                .map(|(_, r, _)| r.y + r.height)
+                .fold(f32::NEG_INFINITY, f32::max);
+
+            (
+                [left - 20.0, top - 44.0],
+                [right - left + 40.0, bottom - top + 64.0],
+            )
+        } else {
+            let size = match kind {
+                AnnotationKind::Note => [200.0, 140.0],
+                AnnotationKind::Frame => [320.0, 240.0],
+            };
+            ([center.x - size[0] / 2.0, center.y - size[1] / 2.0], size)
+        };
+
+        let title = self
+            .translations
+            .borrow()
+            .get_gui_translation(&self.settings.borrow().language, "annotation.frame.title");
+        let save = AnnotationSave {
+            id: Self::allocate_node_id(state, kind.name()),
+            kind,
+            position,
+            size,
+            text: match kind {
+                AnnotationKind::Note => String::new(),
+                AnnotationKind::Frame => title,
+            },
+        };
+
+        self.spawn_annotation(&save, state);
+        state.history.push(Command::AddAnnotation(save));
+    }
+
+    fn delete_annotations(&mut self, ids: &[String], state: &mut EditorState) {
+        let mut commands: Vec<Command> = ids
+            .iter()
+            .filter_map(|id| {
+                let obj = self.objects.remove(id)?;
+                let obj = obj.borrow();
+                let annotation = obj.as_any().downcast_ref::<Annotation>()?;
+                Some(Command::RemoveAnnotation(AnnotationSave::from_annotation(
+                    annotation,
+                )))
+            })
+            .collect();
+
+        if commands.len() == 1 {
+            state.history.push(commands.pop().unwrap());
+        } else if !commands.is_empty() {
+            state.history.push(Command::Group(commands));
+        }
+    }
+
+    /// The annotation and, for a frame, every node and note lying inside it.
+    fn annotation_group(
+        objects: &HashMap<String, Rc<RefCell<dyn Object>>>,
+        id: &str,
+    ) -> Vec<String> {
+        let mut group = vec![id.to_string()];
+        let Some(frame) = objects.get(id).and_then(|obj| {
+            let obj = obj.borrow();
+            let annotation = obj.as_any().downcast_ref::<Annotation>()?;
+            (annotation.kind == AnnotationKind::Frame).then(|| annotation.rect())
+        }) else {
+            return group;
+        };
+
+        let inside = |rect: Rectangle| {
+            rect.x >= frame.x
+                && rect.y >= frame.y
+                && rect.x + rect.width <= frame.x + frame.width
+                && rect.y + rect.height <= frame.y + frame.height
+        };
+
+        for (other, obj) in objects {
+            let obj = obj.borrow();
+            let rect = if let Some(node) = obj.as_any().downcast_ref::<Node>() {
+                Some(node.bounds())
+            } else {
+                obj.as_any()
+                    .downcast_ref::<Annotation>()
+                    .filter(|annotation| annotation.kind == AnnotationKind::Note)
+                    .map(Annotation::rect)
+            };
+
+            if other != id && rect.is_some_and(inside) {
+                group.push(other.clone());
+            }
+        }
+        group
+    }
+
+    /// The selected nodes and the connections between them, as a save file fragment.
+    fn selection_fragment(&self, state: &EditorState) -> Option<SaveFile> {
+        if state.selection.is_empty() {
+            return None;
+        }
+
+        let nodes: Vec<NodeSave> = state
+            .selection
+            .iter()
+            .filter_map(|id| {
+                let obj = self.objects.get(id)?.borrow();
+                obj.as_any().downcast_ref::<Node>().map(NodeSave::from_node)
+            })
+            .collect();
+        let mut connections: Vec<ConnectionKey> = state
+            .connections
+            .keys()
+            .filter(|key| {
+                state.selection.contains(&key.from_node) && state.selection.contains(&key.to_node)
+            })
+            .cloned()
+            .collect();
+        connections.sort();
+
+        let mut fragment = SaveFile::from(
+            state.project_name.clone(),
+            nodes,
+            connections,
+            CameraSave {
+                position: [0.0, 0.0],
+                zoom: 1.0,
+            },
+        );
+        fragment.wires = Self::wire_saves(state, &fragment.connections);
+        Some(fragment)
+    }
+
+    fn copy_selection(&self, rl: &mut RaylibHandle, state: &EditorState) {
+        let Some(fragment) = self.selection_fragment(state) else {
+            return;
+        };
+
+        match serde_json::to_string_pretty(&fragment) {
+            Ok(text) => {
+                if let Err(e) = rl.set_clipboard_text(&text) {
+                    eprintln!("Failed to copy nodes: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Failed to copy nodes: {}", e),
+        }
+    }
+
+    /// Adds the fragment's nodes under fresh ids, with their top left corner at `at`.
+    fn paste_fragment(&mut self, fragment: SaveFile, at: Vector2, state: &mut EditorState) {
+        if fragment.nodes.is_empty() {
+            return;
+        }
+
+        let min_x = fragment
+            .nodes
+            .iter()
+            .map(|node| node.position[0])
+            .fold(f32::INFINITY, f32::min);
+        let min_y = fragment
+            .nodes
+            .iter()
+            .map(|node| node.position[1])
+            .fold(f32::INFINITY, f32::min);
+
+        let mut ids: HashMap<String, String> = HashMap::new();
+        let nodes: Vec<NodeSave> = fragment
+            .nodes
+            .into_iter()
+            .map(|mut node| {
+                let id = Self::allocate_node_id(state, &node.type_name);
+                ids.insert(node.id.clone(), id.clone());
+                node.id = id;
+                node.position = [
+                    at.x + node.position[0] - min_x,
+                    at.y + node.position[1] - min_y,
+                ];
+                node
+            })
+            .collect();
+        let remap = |key: ConnectionKey| {
+            Some(ConnectionKey {
+                from_node: ids.get(&key.from_node)?.clone(),
+                from_port: key.from_port,
+                to_node: ids.get(&key.to_node)?.clone(),
+                to_port: key.to_port,
+            })
+        };
+        let connections: Vec<ConnectionKey> =
+            fragment.connections.into_iter().filter_map(remap).collect();
+        let wires: Vec<WireSave> = fragment
+            .wires
+            .into_iter()
+            .filter_map(|wire| {
+                Some(WireSave {
+                    connection: remap(wire.connection)?,
+                    ..wire
+                })
+            })
+            .collect();
+
+        for node in &nodes {
+            self.spawn_node(node, &connections, state);
+        }
+        let connections: Vec<ConnectionKey> = connections
+            .into_iter()
+            .filter(|key| {
+                state.detached_wires.remove(key);
+                self.connect(key, state)
+            })
+            .collect();
+        Self::restore_wires(
+            &wires,
+            Vector2::new(at.x - min_x, at.y - min_y, None),
+            state,
+        );
+
+        state.selection = nodes.iter().map(|node| node.id.clone()).collect();
+        state.evaluation_requested = true;
+        state.history.push(Command::AddNodes { nodes, connections });
+    }
+
+    fn selected_bounds(&self, state: &EditorState) -> Vec<(String, Rectangle, f32)> {
+        state
+            .selection
+            .iter()
+            .filter_map(|id| {
+                let obj = self.objects.get(id)?.borrow();
+                let node = obj.as_any().downcast_ref::<Node>()?;
+                Some((id.clone(), node.bounds(), node.title_height))
+            })
+            .collect()
+    }
+
+    /// Lines up the selected nodes' bounds on the outermost `edge` (left, right, top or bottom).
+    fn align_selection(&mut self, edge: &str, state: &mut EditorState) {
+        let nodes = self.selected_bounds(state);
+        if nodes.len() < 2 {
+            return;
+        }
+
+        let left = nodes
+            .iter()
+            .map(|(_, r, _)| r.x)
+            .fold(f32::INFINITY, f32::min);
+        let right = nodes
+            .iter()
+            .map(|(_, r, _)| r.x + r.width)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let top = nodes
+            .iter()
+            .map(|(_, r, _)| r.y)
+            .fold(f32::INFINITY, f32::min);
+        let bottom = nodes
+            .iter()
+            .map(|(_, r, _)| r.y + r.height)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        let targets = nodes
+            .into_iter()
+            .map(|(id, rect, title_height)| {
+                let (x, y) = match edge {
+                    "left" => (left, rect.y),
+                    "right" => (right - rect.width, rect.y),
+                    "top" => (rect.x, top),
+                    "bottom" => (rect.x, bottom - rect.height),
+                    _ => (rect.x, rect.y),
+                };
+                (id, Vector2::new(x, y + title_height, None))
+            })
+            .collect();
+        self.move_nodes_to(targets, state);
+    }
+
+    /// Spreads the selected nodes so the gaps between their bounds are equal.
+    fn distribute_selection(&mut self, horizontal: bool, state: &mut EditorState) {
+        let mut nodes = self.selected_bounds(state);
+        if nodes.len() < 3 {
+            return;
+        }
+
+        let start = |r: &Rectangle| if horizontal { r.x } else { r.y };
+        let length = |r: &Rectangle| if horizontal { r.width } else { r.height };
+        nodes.sort_by(|(_, a, _), (_, b, _)| start(a).total_cmp(&start(b)));
+
+        let first = start(&nodes[0].1);
+        let end = nodes
+            .iter()
+            .map(|(_, r, _)| start(r) + length(r))
+            .fold(f32::NEG_INFINITY, f32::max);
+        let total: f32 = nodes.iter().map(|(_, r, _)| length(r)).sum();
+        let gap = (end - first - total) / (nodes.len() - 1) as f32;
+
+        let mut cursor = first;
+        let targets = nodes
+            .into_iter()
+            .map(|(id, rect, title_height)| {
+                let (x, y) = if horizontal {
+                    (cursor, rect.y)
+                } else {
+                    (rect.x, cursor)
+                };
+                cursor += length(&rect) + gap;
+                (id, Vector2::new(x, y + title_height, None))
+            })
+            .collect();
+        self.move_nodes_to(targets, state);
+    }
+
+    /// Lays out the selection, or every node when nothing is selected, in dependency layers.
+    fn auto_arrange(&mut self, state: &mut EditorState) {
+        let nodes: Vec<(String, Rectangle, f32)> = self
+            .objects
+            .iter()
+            .filter(|(id, _)| state.selection.is_empty() || state.selection.contains(*id))
+            .filter_map(|(id, obj)| {
+                let obj = obj.borrow();
+                let node = obj.as_any().downcast_ref::<Node>()?;
+                Some((id.clone(), node.bounds(), node.title_height))
+            })
+            .collect();
+        if nodes.is_empty() {
+            return;
+        }
+
+        let left = nodes
+            .iter()
+            .map(|(_, r, _)| r.x)
+            .fold(f32::INFINITY, f32::min);
+        let top = nodes
+            .iter()
+            .map(|(_, r, _)| r.y)
+            .fold(f32::INFINITY, f32::min);
+        let mut origin = Vector2::new(left, top, None);
+        {
+            let settings = self.settings.borrow();
+            if settings.snap_to_grid {
+                origin = settings.snap_position(origin);
+            }
+        }
+
+        let layout_nodes: Vec<LayoutNode> = nodes
+            .iter()
+            .map(|(id, rect, _)| LayoutNode {
+                id: id.clone(),
+                size: Vector2::new(rect.width, rect.height, None),
+                y: rect.y,
+            })
+            .collect();
+        let edges: Vec<(String, String)> = state
+            .connections
+            .keys()
+            .map(|key| (key.from_node.clone(), key.to_node.clone()))
+            .collect();
+        let positions = graph::layered_layout(&layout_nodes, &edges);
+
+        let targets = nodes
+            .into_iter()
+            .filter_map(|(id, _, title_height)| {
+                let corner = positions.get(&id)?.clone();
+                let position = origin.clone() + corner + Vector2::new(0.0, title_height, None);
+                Some((id, position))
+            })
+            .collect();
+        self.move_nodes_to(targets, state);
+    }
+
+    fn move_nodes_to(&mut self, targets: Vec<(String, Vector2)>, state: &mut EditorState) {
+        let moves: Vec<(String, Vector2, Vector2)> = targets
+            .into_iter()
+            .filter_map(|(id, to)| {
+                let from = Self::node_position(&self.objects, &id)?;
+                (from != to).then_some((id, from, to))
+            })
+            .collect();
+        if moves.is_empty() {
+            return;
+        }
+
+        let command = Command::MoveNodes(moves);
+        self.apply_command(&command, state);
+        state.history.push(command);
+    }
+
+    fn context_items(&self, target: &ContextTarget) -> Vec<ToolBarItem> {
+        let entry = |label: &str, text: Option<String>| ToolBarItem {
+            label: label.to_string(),
+            text,
+            on_click: Some(label.to_string()),
+            children: vec![],
+            expanded: false,
+        };
+
+        match target {
+            ContextTarget::Node { id, .. } => {
+                let node = self.objects.get(id);
+                let collapsed = node.is_some_and(|obj| {
+                    obj.borrow()
+                        .as_any()
+                        .downcast_ref::<Node>()
+                        .is_some_and(|node| node.collapsed)
+                });
+                let mut items: Vec<ToolBarItem> = [
+                    "delete",
+                    "duplicate",
+                    "rename",
+                    if collapsed { "expand" } else { "collapse" },
+                    "bypass",
+                    "help",
+                ]
+                .into_iter()
+                .map(|label| entry(label, None))
+                .collect();
+                items.extend(
+                    std::iter::once("none")
+                        .chain(NODE_COLORS)
+                        .map(|color| entry(&format!("color.{}", color), None)),
+                );
+                if let Some(obj) = node
+                    && let Some(node) = obj.borrow().as_any().downcast_ref::<Node>()
+                {
+                    for (i, (text, _)) in node.menu_items.iter().enumerate() {
+                        items.push(entry(&format!("module:{}", i), Some(text.clone())));
+                    }
+                }
+                items
+            }
+            ContextTarget::Wire { reroute, .. } => vec![
+                entry("delete", None),
+                entry(
+                    if reroute.is_some() {
+                        "remove_reroute"
+                    } else {
+                        "insert_reroute"
+                    },
+                    None,
+                ),
+            ],
+            ContextTarget::Annotation { .. } => {
+                vec![entry("delete", None), entry("edit_text", None)]
+            }
+            ContextTarget::Canvas { .. } => vec![entry("add_node", None), entry("paste", None)],
+        }
+    }
+
+    fn open_context_menu(
+        &mut self,
+        target: ContextTarget,
+        mouse: Vector2,
+        screen: Vector2,
+        state: &mut EditorState,
+    ) {
+        /* Node actions apply to the selection, which the clicked node joins unless it is in it */
+        if let ContextTarget::Node { id, .. } = &target
+            && !state.selection.contains(id)
+        {
+            state.selection = BTreeSet::from([id.clone()]);
+        }
+
+        let items = self.context_items(&target);
+        if let Some(menu) = &mut self.context_menu {
+            menu.show(mouse, items, screen);
+            self.context_target = Some(target);
+        }
+    }
+
+    fn run_context_action(
+        &mut self,
+        rl: &mut RaylibHandle,
+        event: &str,
+        target: ContextTarget,
+        state: &mut EditorState,
+    ) {
+        match (target, event) {
+            (ContextTarget::Node { .. }, "delete") => {
+                let ids: Vec<String> = state.selection.iter().cloned().collect();
+                self.delete_nodes(&ids, state);
+            }
+            (ContextTarget::Node { at, .. }, "duplicate") => {
+                if let Some(fragment) = self.selection_fragment(state) {
+                    self.paste_fragment(fragment, at, state);
+                }
+            }
+            (ContextTarget::Node { id, .. }, "rename") => {
+                if let Some(obj) = self.objects.get(&id)
+                    && let Some(node) = obj.borrow_mut().as_any_mut().downcast_mut::<Node>()
+                {
+                    node.begin_rename();
+                }
+            }
+            (ContextTarget::Node { .. }, "collapse" | "expand") => self.toggle_collapsed(state),
+            (ContextTarget::Node { .. }, "bypass") => self.toggle_bypass(state),
+            (ContextTarget::Node { id, .. }, "help") => self.show_node_help(&id, state),
+            (ContextTarget::Node { .. }, event) if event.starts_with("color.") => {
+                let color = event.trim_start_matches("color.");
+                self.set_node_colors((color != "none").then(|| color.to_string()), state);
+            }
+            (ContextTarget::Node { id, .. }, event) => {
+                let Some(index) = event
+                    .strip_prefix("module:")
+                    .and_then(|index| index.parse::<usize>().ok())
+                else {
+                    return;
+                };
+                if let Some(obj) = self.objects.get(&id)
+                    && let Some(node) = obj.borrow_mut().as_any_mut().downcast_mut::<Node>()
+                {
+                    node.run_menu_item(index);
+                    state.evaluation_requested = true;
+                    state.dirty = true;
+                    state.autosave_pending = true;
+                }
+            }
+            (ContextTarget::Wire { key, .. }, "delete") => {
+                Self::remove_connection(&key, state);
+                state.history.push(Command::RemoveConnection(key));
+            }
+            (
+                ContextTarget::Wire {
+                    key, segment, at, ..
+                },
+                "insert_reroute",
+            ) => {
+                if let Some(conn) = state.connections.get(&key) {
+                    let mut reroutes = conn.reroutes.clone();
+                    reroutes.insert(segment.min(reroutes.len()), at);
+                    Self::set_reroutes(key, reroutes, state);
+                }
+            }
+            (
+                ContextTarget::Wire {
+                    key,
+                    reroute: Some(index),
+                    ..
+                },
+                "remove_reroute",
+            ) => {
+                if let Some(conn) = state.connections.get(&key) {
+                    let mut reroutes = conn.reroutes.clone();
+                    reroutes.remove(index);
+                    Self::set_reroutes(key, reroutes, state);
+                }
+            }
+            (ContextTarget::Annotation { id }, "delete") => {
+                self.delete_annotations(&[id], state);
+            }
+            (ContextTarget::Annotation { id }, "edit_text") => {
+                if let Some(obj) = self.objects.get(&id)
+                    && let Some(annotation) =
+                        obj.borrow_mut().as_any_mut().downcast_mut::<Annotation>()
+                {
+                    annotation.begin_text_edit();
+                }
+            }
+            (ContextTarget::Canvas { at }, "add_node") => {
+                let screen = Vector2::new(
+                    rl.get_screen_width() as f32,
+                    rl.get_screen_height() as f32,
+                    None,
+                );
+                let position = self
+                    .context_menu
+                    .as_ref()
+                    .map(|menu| menu.position.clone())
+                    .unwrap_or_else(Vector2::zero);
+                if let Some(palette) = &mut self.node_palette {
+                    palette.show(position, at, screen, |_| true);
+                }
+            }
+            (ContextTarget::Canvas { at }, "paste") => {
+                if let Ok(text) = rl.get_clipboard_text()
+                    && let Ok(fragment) = SaveFile::parse(&text)
+                {
+                    self.paste_fragment(fragment, at, state);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Bypasses the selected nodes, or lets them run again when all of them already are.
+    fn toggle_bypass(&mut self, state: &mut EditorState) {
+        let selected: Vec<(String, bool)> = state
+            .selection
+            .iter()
+            .filter_map(|id| {
+                let obj = self.objects.get(id)?.borrow();
+                let node = obj.as_any().downcast_ref::<Node>()?;
+                Some((id.clone(), node.bypassed))
+            })
+            .collect();
+        let bypass = !selected.iter().all(|(_, bypassed)| *bypassed);
+
+        let changes: Vec<(String, bool)> = selected
+            .into_iter()
+            .filter(|(_, bypassed)| *bypassed != bypass)
+            .map(|(id, _)| (id, bypass))
+            .collect();
+        if changes.is_empty() {
+            return;
+        }
+
+        let command = Command::SetBypass(changes);
+        self.apply_command(&command, state);
+        state.history.push(command);
+    }
+
+    /// Collapses the selected nodes, or expands them when all of them already are.
+    fn toggle_collapsed(&mut self, state: &mut EditorState) {
+        let selected: Vec<(String, bool)> = state
+            .selection
+            .iter()
+            .filter_map(|id| {
+                let obj = self.objects.get(id)?.borrow();
+                let node = obj.as_any().downcast_ref::<Node>()?;
+                Some((id.clone(), node.collapsed))
+            })
+            .collect();
+        let collapse = !selected.iter().all(|(_, collapsed)| *collapsed);
+
+        let changes: Vec<(String, bool)> = selected
+            .into_iter()
+            .filter(|(_, collapsed)| *collapsed != collapse)
+            .map(|(id, _)| (id, collapse))
+            .collect();
+        if changes.is_empty() {
+            return;
+        }
+
+        let command = Command::SetCollapsed(changes);
+        self.apply_command(&command, state);
+        state.history.push(command);
+    }
+
+    /// Gives every selected node the color tag `color`, or takes it away with `None`.
+    fn set_node_colors(&mut self, color: Option<String>, state: &mut EditorState) {
+        let changes: Vec<(String, Option<String>, Option<String>)> = state
+            .selection
+            .iter()
+            .filter_map(|id| {
+                let obj = self.objects.get(id)?.borrow();
+                let node = obj.as_any().downcast_ref::<Node>()?;
+                (node.color != color).then(|| (id.clone(), node.color.clone(), color.clone()))
+            })
+            .collect();
+        if changes.is_empty() {
+            return;
+        }
+
+        let command = Command::SetNodeColors(changes);
+        self.apply_command(&command, state);
+        state.history.push(command);
+    }
+
+    fn show_node_help(&self, id: &str, state: &mut EditorState) {
+        let Some(type_name) = self.objects.get(id).and_then(|obj| {
+            obj.borrow()
+                .as_any()
+                .downcast_ref::<Node>()
+                .map(|node| node.type_name.clone())
+        }) else {
+            return;
+        };
+        let text = self
+            .translations
+            .borrow()
+            .get_node_translation(&self.settings.borrow().language, &type_name);
+
+        let mut dialog = Dialog::new(
+            "node.help".to_string(),
+            vec![(DialogButton::Ok, "dialog.close".to_string())],
+            self.color_schemes.clone(),
+            self.settings.clone(),
+            self.translations.clone(),
+            self.active_font.clone().unwrap(),
+        );
+        dialog.detail = Some(format!("{}: {}", text.title, text.description));
+        state.dialog = Some(dialog);
+    }
+
+    fn delete_nodes(&mut self, ids: &[String], state: &mut EditorState) {
+        let nodes: Vec<NodeSave> = ids
+            .iter()
+            .filter_map(|id| {
+                let obj = self.objects.get(id)?.borrow();
+                obj.as_any().downcast_ref::<Node>().map(NodeSave::from_node)
+            })
+            .collect();
+        let connections = Self::node_connections(state, ids);
+
+        self.remove_nodes(ids, state);
+        state
+            .history
+            .push(Command::RemoveNodes { nodes, connections });
+    }
+
+    fn apply_command(&mut self, command: &Command, state: &mut EditorState) {
+        match command {
+            Command::AddNodes { nodes, connections } => {
+                for node in nodes {
+                    self.spawn_node(node, connections, state);
+                }
+                for key in connections {
+                    self.connect(key, state);
+                }
+            }
+            Command::RemoveNodes { nodes, .. } => {
+                let ids: Vec<String> = nodes.iter().map(|n| n.id.clone()).collect();
+                self.remove_nodes(&ids, state);
+            }
+            Command::MoveNodes(moves) => {
+                for (id, _, to) in moves {
+                    if let Some(obj) = self.objects.get(id) {
+                        obj.borrow_mut()
+                            .set_property("position".to_string(), Box::new(to.clone()));
+                    }
+                }
+            }
+            Command::AddConnection(key) => {
+                self.connect(key, state);
+            }
+            Command::RemoveConnection(key) => Self::remove_connection(key, state),
+            Command::EditComponents { node_id, after, .. } => {
+                if let Some(obj) = self.objects.get(node_id) {
+                    let mut obj = obj.borrow_mut();
+                    if let Some(node) = obj.as_any_mut().downcast_mut::<Node>() {
+                        node.apply_component_values(after);
+                    }
+                }
+            }
+            Command::AddAnnotation(save) => self.spawn_annotation(save, state),
+            Command::RemoveAnnotation(save) => {
+                self.objects.remove(&save.id);
+            }
+            Command::EditAnnotation { after, .. } => {
+                if let Some(obj) = self.objects.get(&after.id) {
+                    let mut obj = obj.borrow_mut();
+                    if let Some(annotation) = obj.as_any_mut().downcast_mut::<Annotation>() {
+                        annotation.apply(after);
+                    }
+                }
+            }
+            Command::SetReroutes { key, after, .. } => {
+                if let Some(conn) = state.connections.get_mut(key) {
+                    conn.reroutes = after.clone();
+                }
+            }
+            Command::SetWireStyle { key, after, .. } => {
+                if let Some(conn) = state.connections.get_mut(key) {
+                    conn.style = *after;
+                }
+            }
+            Command::SetBypass(nodes) => {
+                for (id, bypassed) in nodes {
+                    if let Some(obj) = self.objects.get(id)
+                        && let Some(node) = obj.borrow_mut().as_any_mut().downcast_mut::<Node>()
+                    {
+                        node.bypassed = *bypassed;
+                    }
+                }
+                state.evaluation_requested = true;
+            }
+            Command::SetCollapsed(nodes) => {
+                for (id, collapsed) in nodes {
+                    if let Some(obj) = self.objects.get(id)
+                        && let Some(node) = obj.borrow_mut().as_any_mut().downcast_mut::<Node>()
+                    {
+                        node.collapsed = *collapsed;
+                    }
+                }
+            }
+            Command::RenameNode { id, after, .. } => {
+                if let Some(obj) = self.objects.get(id)
+                    && let Some(node) = obj.borrow_mut().as_any_mut().downcast_mut::<Node>()
+                {
+                    node.title = after.clone();
+                }
+            }
+            Command::SetNodeColors(nodes) => {
+                for (id, _, after) in nodes {
+                    if let Some(obj) = self.objects.get(id)
+                        && let Some(node) = obj.borrow_mut().as_any_mut().downcast_mut::<Node>()
+                    {
+                        node.color = after.clone();
+                    }
+                }
+            }
+            Command::Group(commands) => {
+                for command in commands {
+                    self.apply_command(command, state);
+                }
+            }
+        }
+
+        state.evaluation_requested = true;
+    }
+
+    fn undo(&mut self, state: &mut EditorState) {
+        if let Some(command) = state.history.undo() {
+            self.apply_command(&command, state);
+        }
+    }
+
+    fn redo(&mut self, state: &mut EditorState) {
+        if let Some(command) = state.history.redo() {
+            self.apply_command(&command, state);
+        }
+    }
+
+    pub fn find_port(
+        &self,
+        node_id: &str,
+        port_label: &str,
+    ) -> Option<Rc<RefCell<Box<crate::node::Port>>>> {
+        for (id, obj) in &self.objects {
+            if id.clone() == node_id.to_string() {
+                let node_ref = obj.borrow();
+                if let Some(node) = node_ref.as_any().downcast_ref::<Node>() {
+                    for (label, _, _, port) in &node.ports {
+                        if label == port_label {
+                            return Some(port.clone());
+                        }
+                    }
+                } else {
+                }
+            }
+        }
+        None
+    }
+
+    pub fn open_project(&mut self, state: &mut EditorState, path: Option<String>) {
+        let Some(path) = path.or_else(SaveFile::open_dialog) else {
+            return;
+        };
+
+        match SaveFile::from_file(&path) {
+            Ok(save) => {
+                /* The changes to the previous project were declined in the open dialog */
+                Self::discard_recovery(state.project_path.as_deref());
+                self.load_from_save(save, state);
+                state.project_path = Some(path.clone());
+                self.add_recent_file(&path);
+            }
+            Err(message) => {
+                eprintln!("{}", message);
+
+                let mut dialog = Dialog::new(
+                    "file.load_error".to_string(),
+                    vec![(DialogButton::Ok, "dialog.close".to_string())],
+                    self.color_schemes.clone(),
+                    self.settings.clone(),
+                    self.translations.clone(),
+                    self.active_font.clone().unwrap(),
+                );
+                dialog.detail = Some(message);
+                state.dialog = Some(dialog);
+            }
+        }
+    }
+
+    fn clear_project(&mut self, state: &mut EditorState) {
+        state.connections.clear();
+        state.node_names.clear();
+        state.history.clear();
+        state.selection.clear();
+        state.marquee = None;
+        state.detached_wires.clear();
+        state.dirty = false;
+        state.autosave_pending = false;
+        state.save_file = None;
+        state.project_name = "untitled".to_string();
+        Self::discard_recovery(state.project_path.as_deref());
+        state.project_path = None;
+        self.objects.retain(|name, _| name == "grid");
+
+        let mut cam = self.camera.borrow_mut();
+        cam.target = Vector2::zero();
+        cam.zoom = 1.0;
+    }
+
+    fn add_recent_file(&self, path: &str) {
+        let mut settings = self.settings.borrow_mut();
+        settings.recent_files.retain(|recent| recent != path);
+        settings.recent_files.insert(0, path.to_string());
+        settings.recent_files.truncate(RECENT_FILES_LIMIT);
+
+        if let Err(e) = settings.save_user_settings() {
+            eprintln!("Recent files could not be saved: {}", e);
+        }
+    }
+
+    fn refresh_recent_menu(&mut self) {
+        let recent_files = self.settings.borrow().recent_files.clone();
+        let Some(menu) = self
+            .tool_bar
+            .as_mut()
+            .and_then(|tool_bar| tool_bar.find_item("recent"))
+        else {
+            return;
+        };
+
+        let shown: Vec<Option<&String>> = menu.children.iter().map(|c| c.text.as_ref()).collect();
+        let wanted: Vec<Option<&String>> = if recent_files.is_empty() {
+            vec![None]
+        } else {
+            recent_files.iter().map(Some).collect()
+        };
+        if shown == wanted {
+            return;
+        }
+
+        menu.children = if recent_files.is_empty() {
+            vec![ToolBarItem {
+                label: "empty".to_string(),
+                text: None,
+                on_click: None,
+                children: vec![],
+                expanded: false,
+            }]
+        } else {
+            recent_files
+                .iter()
+                .enumerate()
+                .map(|(i, path)| ToolBarItem {
+                    label: i.to_string(),
+                    text: Some(path.clone()),
+                    on_click: Some(format!("open_recent:{}", path)),
+                    children: vec![],
+                    expanded: false,
+                })
+                .collect()
+        };
+    }
+
+    fn exit(&self) {
+        EDITOR_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+
+            if !state.dirty {
+                state.exit_requested = true;
+                return;
+            }
+
+            state.dialog = Some(Dialog::new(
+                "file.exit".to_string(),
+                vec![
+                    (DialogButton::Yes, "file.exit_save".to_string()),
+                    (DialogButton::No, "file.exit".to_string()),
+                    (DialogButton::Cancel, "dialog.close".to_string()),
+                ],
+                self.color_schemes.clone(),
+                self.settings.clone(),
+                self.translations.clone(),
+                self.active_font.clone().unwrap(),
+            ))
+        })
     }
 
     fn new_file(&self) {
@@ -897,7 +3128,7 @@ impl Window {
                 vec![
                     (DialogButton::Yes, "file.new_save".to_string()),
                     (DialogButton::No, "file.new".to_string()),
-                    (DialogButton::Cancel, "".to_string()),
+                    (DialogButton::Cancel, "dialog.close".to_string()),
                 ],
                 self.color_schemes.clone(),
                 self.settings.clone(),